@@ -0,0 +1,130 @@
+//! Compares sending and receiving DTLS records with one system call per datagram against
+//! `UdpBatch` on loopback.
+//!
+//! Run with `cargo bench --bench udp_batch`.
+
+use std::{
+    io::{Read, Write},
+    net::UdpSocket,
+    time::{Duration, Instant},
+};
+use udp_dtls::{
+    Certificate, CertificateIdentity, DtlsAcceptor, DtlsConnector, DtlsSession, UdpBatch,
+    MAX_DATAGRAM_SIZE,
+};
+
+/// Datagrams sent per round, small enough for the default socket receive buffer.
+const BATCH: usize = 32;
+const ROUNDS: usize = 2_000;
+const RECORD_SIZE: usize = 1_200;
+
+fn sessions() -> (DtlsSession, DtlsSession) {
+    let identity =
+        CertificateIdentity::from_pkcs12(include_bytes!("../test/server.p12"), "mypass").unwrap();
+    let root = Certificate::from_der(include_bytes!("../test/server.der")).unwrap();
+
+    let acceptor = DtlsAcceptor::builder(identity).build().unwrap();
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(root)
+        .build()
+        .unwrap();
+
+    let mut client = connector.connect_session("foobar.com").unwrap();
+    let mut server = acceptor.accept_session().unwrap();
+    while client.is_handshaking() || server.is_handshaking() {
+        while let Some(datagram) = client.poll_datagram() {
+            server.handle_datagram(&datagram).unwrap();
+        }
+        while let Some(datagram) = server.poll_datagram() {
+            client.handle_datagram(&datagram).unwrap();
+        }
+    }
+    (client, server)
+}
+
+fn sockets() -> (UdpSocket, UdpSocket) {
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+    sender.connect(receiver.local_addr().unwrap()).unwrap();
+    (sender, receiver)
+}
+
+/// Sends each round of records with `send` and receives it with `recv`, returning the elapsed
+/// time.
+fn run(
+    mut send: impl FnMut(&mut DtlsSession),
+    mut recv: impl FnMut(&mut DtlsSession) -> usize,
+) -> Duration {
+    let (mut client, mut server) = sessions();
+    let record = [0x5a; RECORD_SIZE];
+    let mut buf = [0; MAX_DATAGRAM_SIZE];
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for _ in 0..BATCH {
+            client.write_all(&record).unwrap();
+        }
+        send(&mut client);
+
+        let mut received = 0;
+        while received < BATCH {
+            received += recv(&mut server);
+        }
+        for _ in 0..BATCH {
+            assert_eq!(server.read(&mut buf).unwrap(), RECORD_SIZE);
+        }
+    }
+    start.elapsed()
+}
+
+fn report(name: &str, elapsed: Duration) {
+    let records = (ROUNDS * BATCH) as f64;
+    println!(
+        "{:<24} {:>8.0} ms {:>10.0} records/s",
+        name,
+        elapsed.as_secs_f64() * 1000.0,
+        records / elapsed.as_secs_f64()
+    );
+}
+
+fn main() {
+    let (sender, receiver) = sockets();
+    let mut buf = vec![0; 65_535];
+    let single = run(
+        |client| {
+            while let Some(datagram) = client.poll_datagram() {
+                sender.send(&datagram).unwrap();
+            }
+        },
+        |server| {
+            let len = receiver.recv(&mut buf).unwrap();
+            server.handle_datagram(&buf[..len]).unwrap();
+            1
+        },
+    );
+    report("one datagram per call", single);
+
+    let (sender, receiver) = sockets();
+    let peer = receiver.local_addr().unwrap();
+    let mut sender = UdpBatch::new(sender, BATCH);
+    let mut receiver = UdpBatch::new(receiver, BATCH);
+    let batched = run(
+        |client| {
+            sender.queue_session(peer, client);
+            sender.flush().unwrap();
+        },
+        |server| {
+            let received = receiver.recv().unwrap();
+            for (_, datagram) in receiver.received() {
+                server.handle_datagram(datagram).unwrap();
+            }
+            received
+        },
+    );
+    report("UdpBatch", batched);
+
+    println!(
+        "speedup                  {:>8.2}x",
+        single.as_secs_f64() / batched.as_secs_f64()
+    );
+}
@@ -1,22 +1,19 @@
-use openssl::ssl::SslMethod;
-use std::{
-    io::{Read, Write},
-    net::UdpSocket,
-    thread,
-    time::Duration,
-};
+use std::{net::UdpSocket, thread, time::Duration};
 
-use udp_dtls::{Certificate, DtlsAcceptor, DtlsConnector, Identity, SrtpProfile};
-use udp_dtls::{DtlsAcceptorBuilder, UdpChannel};
+use udp_dtls::{Certificate, CertificateIdentity, DtlsAcceptor, DtlsConnector, SrtpProfile};
+use udp_dtls::{UdpChannel, MAX_DATAGRAM_SIZE};
 
 fn main() {
-    let buffer = include_bytes!("../test/identity.p12");
-    let identity = Identity::from_pkcs12(buffer, "mypass").unwrap();
+    let buffer = include_bytes!("../test/server.p12");
+    let identity = CertificateIdentity::from_pkcs12(buffer, "mypass").unwrap();
 
-    let root_ca = include_bytes!("../test/root-ca.der");
+    let root_ca = include_bytes!("../test/server.der");
     let root_ca = Certificate::from_der(root_ca).unwrap();
 
-    let acceptor = DtlsAcceptor::builder(identity).build().unwrap();
+    let acceptor = DtlsAcceptor::builder(identity)
+        .add_srtp_profile(SrtpProfile::Aes128CmSha180)
+        .build()
+        .unwrap();
     let connector = DtlsConnector::builder()
         .add_srtp_profile(SrtpProfile::Aes128CmSha180)
         .add_srtp_profile(SrtpProfile::AeadAes256Gcm)
@@ -44,31 +41,32 @@ fn main() {
         let mut dtls_server = acceptor.accept(server_channel).unwrap();
 
         let mut count = 0;
+        let mut received = [0; MAX_DATAGRAM_SIZE];
 
-        while true {
-            let mut received = [0; 5];
-
-            dtls_server.read_exact(&mut received);
+        loop {
+            let len = dtls_server.recv_datagram(&mut received).unwrap();
+            if len == 0 {
+                break;
+            }
 
             println!(
                 "{:?} {:?}",
                 count,
-                String::from_utf8_lossy(received.as_ref())
+                String::from_utf8_lossy(&received[..len])
             );
 
-            count = count + 1;
-            thread::sleep(Duration::from_millis(2));
+            count += 1;
         }
     });
 
     let mut dtls_client = connector.connect("foobar.com", client_channel).unwrap();
 
-    while true {
-        let mut buf = [0; 5];
-
-        let buf = b"hello";
-        dtls_client.write_all(buf);
+    for _ in 0..10 {
+        dtls_client.send_datagram(b"hello").unwrap();
 
         thread::sleep(Duration::from_millis(30));
     }
+
+    dtls_client.shutdown().unwrap();
+    guard.join().unwrap();
 }
@@ -0,0 +1,210 @@
+use crate::callbacks;
+use libc::c_int;
+use openssl::{error::ErrorStack, ssl::SslRef};
+use std::{error, fmt, sync::Mutex};
+
+/// The level of an alert.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AlertLevel {
+    /// The session may continue, only `close_notify` and `user_canceled` are sent as warnings.
+    Warning,
+    /// The session is terminated.
+    Fatal,
+}
+
+/// The description of an alert, as defined in RFC5246 and its extensions.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum AlertDescription {
+    CloseNotify,
+    UnexpectedMessage,
+    BadRecordMac,
+    RecordOverflow,
+    HandshakeFailure,
+    BadCertificate,
+    UnsupportedCertificate,
+    CertificateRevoked,
+    CertificateExpired,
+    CertificateUnknown,
+    IllegalParameter,
+    UnknownCa,
+    AccessDenied,
+    DecodeError,
+    DecryptError,
+    ProtocolVersion,
+    InsufficientSecurity,
+    InternalError,
+    InappropriateFallback,
+    UserCanceled,
+    NoRenegotiation,
+    UnsupportedExtension,
+    UnrecognizedName,
+    BadCertificateStatusResponse,
+    UnknownPskIdentity,
+    NoApplicationProtocol,
+    /// An alert description unknown to this crate, contains its code.
+    Other(u8),
+}
+
+impl AlertDescription {
+    /// Returns the description for the given code.
+    pub fn from_code(code: u8) -> AlertDescription {
+        match code {
+            0 => AlertDescription::CloseNotify,
+            10 => AlertDescription::UnexpectedMessage,
+            20 => AlertDescription::BadRecordMac,
+            22 => AlertDescription::RecordOverflow,
+            40 => AlertDescription::HandshakeFailure,
+            42 => AlertDescription::BadCertificate,
+            43 => AlertDescription::UnsupportedCertificate,
+            44 => AlertDescription::CertificateRevoked,
+            45 => AlertDescription::CertificateExpired,
+            46 => AlertDescription::CertificateUnknown,
+            47 => AlertDescription::IllegalParameter,
+            48 => AlertDescription::UnknownCa,
+            49 => AlertDescription::AccessDenied,
+            50 => AlertDescription::DecodeError,
+            51 => AlertDescription::DecryptError,
+            70 => AlertDescription::ProtocolVersion,
+            71 => AlertDescription::InsufficientSecurity,
+            80 => AlertDescription::InternalError,
+            86 => AlertDescription::InappropriateFallback,
+            90 => AlertDescription::UserCanceled,
+            100 => AlertDescription::NoRenegotiation,
+            110 => AlertDescription::UnsupportedExtension,
+            112 => AlertDescription::UnrecognizedName,
+            113 => AlertDescription::BadCertificateStatusResponse,
+            115 => AlertDescription::UnknownPskIdentity,
+            120 => AlertDescription::NoApplicationProtocol,
+            code => AlertDescription::Other(code),
+        }
+    }
+
+    /// Returns the code of this description.
+    pub fn code(self) -> u8 {
+        match self {
+            AlertDescription::CloseNotify => 0,
+            AlertDescription::UnexpectedMessage => 10,
+            AlertDescription::BadRecordMac => 20,
+            AlertDescription::RecordOverflow => 22,
+            AlertDescription::HandshakeFailure => 40,
+            AlertDescription::BadCertificate => 42,
+            AlertDescription::UnsupportedCertificate => 43,
+            AlertDescription::CertificateRevoked => 44,
+            AlertDescription::CertificateExpired => 45,
+            AlertDescription::CertificateUnknown => 46,
+            AlertDescription::IllegalParameter => 47,
+            AlertDescription::UnknownCa => 48,
+            AlertDescription::AccessDenied => 49,
+            AlertDescription::DecodeError => 50,
+            AlertDescription::DecryptError => 51,
+            AlertDescription::ProtocolVersion => 70,
+            AlertDescription::InsufficientSecurity => 71,
+            AlertDescription::InternalError => 80,
+            AlertDescription::InappropriateFallback => 86,
+            AlertDescription::UserCanceled => 90,
+            AlertDescription::NoRenegotiation => 100,
+            AlertDescription::UnsupportedExtension => 110,
+            AlertDescription::UnrecognizedName => 112,
+            AlertDescription::BadCertificateStatusResponse => 113,
+            AlertDescription::UnknownPskIdentity => 115,
+            AlertDescription::NoApplicationProtocol => 120,
+            AlertDescription::Other(code) => code,
+        }
+    }
+}
+
+/// An alert sent or received on a DTLS session.
+///
+/// Returned by `DtlsStream::received_alert` and `DtlsStream::sent_alert`. Reads from a
+/// `DtlsStream` whose peer sent a fatal alert fail with an `io::Error` of kind
+/// `io::ErrorKind::ConnectionAborted` wrapping the `Alert`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Alert {
+    level: AlertLevel,
+    description: AlertDescription,
+}
+
+impl Alert {
+    /// Returns the level of this alert.
+    pub fn level(&self) -> AlertLevel {
+        self.level
+    }
+
+    /// Returns the description of this alert.
+    pub fn description(&self) -> AlertDescription {
+        self.description
+    }
+
+    /// Returns whether this alert closes the session orderly.
+    pub fn is_close_notify(&self) -> bool {
+        self.description == AlertDescription::CloseNotify
+    }
+
+    /// Returns whether this alert terminates the session because of an error.
+    pub fn is_fatal(&self) -> bool {
+        self.level == AlertLevel::Fatal
+    }
+
+    /// Decodes the alert value passed to the info callback, the level in the high byte and the
+    /// description in the low byte.
+    fn from_info_value(value: c_int) -> Alert {
+        Alert {
+            level: if (value >> 8) & 0xff == 2 {
+                AlertLevel::Fatal
+            } else {
+                AlertLevel::Warning
+            },
+            description: AlertDescription::from_code((value & 0xff) as u8),
+        }
+    }
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let level = match self.level {
+            AlertLevel::Warning => "warning",
+            AlertLevel::Fatal => "fatal",
+        };
+        write!(fmt, "{} alert {:?}", level, self.description)
+    }
+}
+
+impl error::Error for Alert {}
+
+/// The last alerts sent and received on a session.
+#[derive(Default)]
+pub(crate) struct Alerts {
+    received: Mutex<Option<Alert>>,
+    sent: Mutex<Option<Alert>>,
+}
+
+impl Alerts {
+    /// Attaches alert recording to `ssl`.
+    pub(crate) fn start(ssl: &mut SslRef) -> Result<(), ErrorStack> {
+        callbacks::set_state(ssl, Alerts::default())?;
+        Ok(())
+    }
+
+    /// Returns the alert recording attached to `ssl`, if any.
+    pub(crate) fn get(ssl: &SslRef) -> Option<&Alerts> {
+        callbacks::state(ssl)
+    }
+
+    /// Records the alert passed to the info callback.
+    pub(crate) fn record(&self, received: bool, value: c_int) {
+        let alert = Alert::from_info_value(value);
+        let slot = if received { &self.received } else { &self.sent };
+        if let Ok(mut slot) = slot.lock() {
+            *slot = Some(alert);
+        }
+    }
+
+    pub(crate) fn received(&self) -> Option<Alert> {
+        self.received.lock().ok().and_then(|alert| *alert)
+    }
+
+    pub(crate) fn sent(&self) -> Option<Alert> {
+        self.sent.lock().ok().and_then(|alert| *alert)
+    }
+}
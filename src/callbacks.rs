@@ -0,0 +1,173 @@
+//! Per session OpenSSL callbacks, dispatching to the state stored in the extra data of the `Ssl`.
+
+use crate::alert::Alerts;
+use crate::keepalive::Keepalive;
+use crate::metrics::Session;
+use crate::openssl::{dtls_set_timer_callback, set_info_callback, set_msg_callback};
+use crate::records::Records;
+use crate::rekey::Rekey;
+use crate::trace::Trace;
+use crate::InvalidRecordPolicy;
+use foreign_types::ForeignTypeRef;
+use libc::{c_int, c_uint, c_void, size_t};
+use openssl::{
+    error::ErrorStack,
+    ex_data::Index,
+    ssl::{Ssl, SslRef},
+};
+use std::{any::TypeId, slice, sync::RwLock};
+
+/// The initial retransmission timeout, as used by OpenSSL without a timer callback.
+const INITIAL_TIMEOUT_US: c_uint = 1_000_000;
+
+/// The maximum retransmission timeout, as used by OpenSSL without a timer callback.
+const MAX_TIMEOUT_US: c_uint = 60_000_000;
+
+const SSL_CB_READ: c_int = 0x04;
+const SSL_CB_ALERT: c_int = 0x4000;
+const SSL_CB_HANDSHAKE_DONE: c_int = 0x20;
+
+/// The extra data indexes allocated for the per session state types, see `set_state`.
+static STATE_INDEXES: RwLock<Vec<(TypeId, c_int)>> = RwLock::new(Vec::new());
+
+/// Stores `state` in the extra data of `ssl`, replacing any previous state of its type.
+///
+/// Each type gets its own extra data index, allocated the first time a state of the type is
+/// stored. The state is dropped with the `Ssl`.
+pub(crate) fn set_state<T: Send + Sync + 'static>(
+    ssl: &mut SslRef,
+    state: T,
+) -> Result<(), ErrorStack> {
+    let index = match state_index::<T>() {
+        Some(index) => index,
+        None => {
+            let mut indexes = STATE_INDEXES.write().unwrap();
+            match find_index(&indexes) {
+                Some(index) => index,
+                None => {
+                    let index = Ssl::new_ex_index::<T>()?;
+                    indexes.push((TypeId::of::<T>(), index.as_raw()));
+                    index
+                }
+            }
+        }
+    };
+    ssl.set_ex_data(index, state);
+    Ok(())
+}
+
+/// Returns the state of type `T` stored in the extra data of `ssl` with `set_state`, if any.
+pub(crate) fn state<T: Send + Sync + 'static>(ssl: &SslRef) -> Option<&T> {
+    state_index::<T>().and_then(|index| ssl.ex_data(index))
+}
+
+fn state_index<T: 'static>() -> Option<Index<Ssl, T>> {
+    find_index(&STATE_INDEXES.read().unwrap())
+}
+
+fn find_index<T: 'static>(indexes: &[(TypeId, c_int)]) -> Option<Index<Ssl, T>> {
+    indexes
+        .iter()
+        .find(|(type_id, _)| *type_id == TypeId::of::<T>())
+        // the index was allocated for `T` by `set_state`
+        .map(|&(_, index)| unsafe { Index::from_raw(index) })
+}
+
+/// Installs the info callback recording the alerts sent and received, and counting completed
+/// handshakes.
+///
+/// Installed on every session created by a connector or acceptor.
+pub(crate) fn install_alert_callback(ssl: &mut SslRef) -> Result<(), ErrorStack> {
+    Alerts::start(ssl)?;
+    set_info_callback(ssl, info_callback);
+    Ok(())
+}
+
+/// Installs the message callback tracking the records dropped by the session.
+///
+/// Installed on every session created by a connector or acceptor.
+pub(crate) fn install_record_callback(
+    ssl: &mut SslRef,
+    policy: InvalidRecordPolicy,
+) -> Result<(), ErrorStack> {
+    Records::start(policy, ssl)?;
+    set_msg_callback(ssl, msg_callback);
+    Ok(())
+}
+
+/// Installs the retransmission timer callback, reporting retransmissions.
+pub(crate) fn install_timer_callback(ssl: &mut SslRef) {
+    dtls_set_timer_callback(ssl, timer_callback);
+}
+
+/// Installs the info and message callbacks, tracing the handshake.
+pub(crate) fn install_trace_callbacks(ssl: &mut SslRef) {
+    set_info_callback(ssl, info_callback);
+    set_msg_callback(ssl, msg_callback);
+}
+
+/// Called by OpenSSL with `0` when the retransmission timer is started, and with the previous
+/// timeout when it expired and the last flight is retransmitted. Doubles the timeout like OpenSSL
+/// does without a callback.
+unsafe extern "C" fn timer_callback(ssl: *mut openssl_sys::SSL, timeout_us: c_uint) -> c_uint {
+    if timeout_us == 0 {
+        return INITIAL_TIMEOUT_US;
+    }
+
+    let ssl = SslRef::from_ptr(ssl);
+    let next = timeout_us.saturating_mul(2).min(MAX_TIMEOUT_US);
+
+    if let Some(session) = Session::get(ssl) {
+        session.retransmission();
+    }
+    if let Some(trace) = Trace::get(ssl) {
+        trace.retransmission(next);
+    }
+
+    next
+}
+
+unsafe extern "C" fn info_callback(ssl: *const openssl_sys::SSL, where_: c_int, value: c_int) {
+    let ssl = SslRef::from_ptr(ssl as *mut openssl_sys::SSL);
+
+    if where_ & SSL_CB_ALERT != 0 {
+        if let Some(alerts) = Alerts::get(ssl) {
+            alerts.record(where_ & SSL_CB_READ != 0, value);
+        }
+    }
+    if where_ & SSL_CB_HANDSHAKE_DONE != 0 {
+        if let Some(rekey) = Rekey::get(ssl) {
+            rekey.handshake_done();
+        }
+        if let Some(keepalive) = Keepalive::get(ssl) {
+            keepalive.received();
+        }
+    }
+    if let Some(trace) = Trace::get(ssl) {
+        trace.info(ssl, where_, value);
+    }
+}
+
+unsafe extern "C" fn msg_callback(
+    write_p: c_int,
+    _version: c_int,
+    content_type: c_int,
+    buf: *const c_void,
+    len: size_t,
+    ssl: *mut openssl_sys::SSL,
+    _arg: *mut c_void,
+) {
+    let ssl = SslRef::from_ptr(ssl);
+    let message = if buf.is_null() {
+        &[][..]
+    } else {
+        slice::from_raw_parts(buf as *const u8, len)
+    };
+
+    if let Some(records) = Records::get(ssl) {
+        records.message(write_p != 0, content_type, message);
+    }
+    if let Some(trace) = Trace::get(ssl) {
+        trace.message(write_p != 0, content_type, message);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{CertificateFingerprint, Error, SignatureAlgorithm};
+use crate::{CertificateFingerprint, Error, PublicKey, SignatureAlgorithm};
 use openssl::{hash::MessageDigest, x509::X509};
 
 /// A wrapper type for an `X509` certificate.
@@ -41,6 +41,17 @@ impl Certificate {
         Ok(der)
     }
 
+    /// Returns the public key of the certificate.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`X509_get_pubkey`].
+    ///
+    /// [`X509_get_pubkey`]: https://www.openssl.org/docs/manmaster/man3/X509_get_pubkey.html
+    pub fn public_key(&self) -> Result<PublicKey, Error> {
+        let key = self.0.public_key()?;
+        Ok(PublicKey::from(key))
+    }
+
     /// Returns the digest of the DER representation of the certificate and the cryptographic hash function used to calculate those bytes.
     ///
     /// # Underlying SSL
@@ -0,0 +1,115 @@
+use crate::Protocol;
+
+/// Named sets of ciphers, groups and signature algorithms.
+///
+/// A preset can be applied to both `DtlsConnectorBuilder` and `DtlsAcceptorBuilder` and replaces
+/// any previously added ciphers, groups and signature algorithms.
+///
+/// More information: https://wiki.mozilla.org/Security/Server_Side_TLS
+#[derive(Hash, Clone, Copy, Eq, PartialEq, Debug)]
+#[non_exhaustive]
+pub enum CipherPreset {
+    /// Only AEAD ciphers with forward secrecy.
+    ///
+    /// Raises the minimum protocol version to DTLS 1.2, the AEAD ciphers are not available in DTLS 1.0.
+    Modern,
+    /// AEAD ciphers with forward secrecy, plus CBC ciphers needed for DTLS 1.0 peers.
+    Intermediate,
+    /// A wide set of ciphers for talking to old or constrained implementations.
+    LegacyCompatible,
+    /// Only pre-shared key ciphers.
+    ///
+    /// Requires a `PskIdentity` on the connector and at least one PSK identity on the acceptor.
+    PskOnly,
+}
+
+impl CipherPreset {
+    /// Replaces the given cipher, group and signature algorithm lists with those of this preset,
+    /// and raises the minimum protocol version if the preset requires it.
+    pub(crate) fn apply(
+        self,
+        ciphers: &mut Vec<String>,
+        groups: &mut Vec<String>,
+        signature_algorithms: &mut Vec<String>,
+        min_protocol: &mut Option<Protocol>,
+    ) {
+        *ciphers = self.ciphers().iter().map(|c| c.to_string()).collect();
+        *groups = self.groups().iter().map(|g| g.to_string()).collect();
+        *signature_algorithms = self
+            .signature_algorithms()
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+
+        if self == CipherPreset::Modern {
+            *min_protocol = Some(Protocol::Dtlsv12);
+        }
+    }
+
+    /// Returns the OpenSSL cipher list of this preset.
+    fn ciphers(self) -> &'static [&'static str] {
+        match self {
+            CipherPreset::Modern => &[
+                "ECDHE-ECDSA-AES128-GCM-SHA256",
+                "ECDHE-RSA-AES128-GCM-SHA256",
+                "ECDHE-ECDSA-AES256-GCM-SHA384",
+                "ECDHE-RSA-AES256-GCM-SHA384",
+                "ECDHE-ECDSA-CHACHA20-POLY1305",
+                "ECDHE-RSA-CHACHA20-POLY1305",
+            ],
+            CipherPreset::Intermediate => &[
+                "ECDHE-ECDSA-AES128-GCM-SHA256",
+                "ECDHE-RSA-AES128-GCM-SHA256",
+                "ECDHE-ECDSA-AES256-GCM-SHA384",
+                "ECDHE-RSA-AES256-GCM-SHA384",
+                "ECDHE-ECDSA-CHACHA20-POLY1305",
+                "ECDHE-RSA-CHACHA20-POLY1305",
+                "DHE-RSA-AES128-GCM-SHA256",
+                "DHE-RSA-AES256-GCM-SHA384",
+                "ECDHE-ECDSA-AES128-SHA",
+                "ECDHE-RSA-AES128-SHA",
+                "ECDHE-ECDSA-AES256-SHA",
+                "ECDHE-RSA-AES256-SHA",
+            ],
+            CipherPreset::LegacyCompatible => &[
+                "HIGH", "MEDIUM", "!aNULL", "!eNULL", "!RC4", "!MD5", "!PSK", "!SRP",
+            ],
+            CipherPreset::PskOnly => &[
+                "PSK-AES128-GCM-SHA256",
+                "PSK-AES256-GCM-SHA384",
+                "PSK-AES128-CCM",
+                "PSK-AES128-CCM8",
+                "ECDHE-PSK-AES128-CBC-SHA256",
+                "PSK-AES128-CBC-SHA256",
+            ],
+        }
+    }
+
+    /// Returns the supported groups (elliptic curves) of this preset.
+    fn groups(self) -> &'static [&'static str] {
+        match self {
+            CipherPreset::Modern | CipherPreset::Intermediate => &["X25519", "P-256", "P-384"],
+            CipherPreset::LegacyCompatible => &["X25519", "P-256", "P-384", "P-521"],
+            CipherPreset::PskOnly => &["X25519", "P-256"],
+        }
+    }
+
+    /// Returns the signature algorithms of this preset.
+    ///
+    /// An empty list leaves the OpenSSL defaults in place.
+    fn signature_algorithms(self) -> &'static [&'static str] {
+        match self {
+            CipherPreset::Modern => &[
+                "ECDSA+SHA256",
+                "ECDSA+SHA384",
+                "rsa_pss_rsae_sha256",
+                "rsa_pss_rsae_sha384",
+                "RSA+SHA256",
+                "RSA+SHA384",
+            ],
+            CipherPreset::Intermediate | CipherPreset::LegacyCompatible | CipherPreset::PskOnly => {
+                &[]
+            }
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{
+    Certificate, CertificateIdentity, ConnectorIdentity, DtlsAcceptor, DtlsAcceptorBuilder,
+    DtlsConnector, DtlsConnectorBuilder, Error, Protocol, Result, SrtpProfile,
+};
+use serde::Deserialize;
+use std::{env, fmt, fs, path::Path, path::PathBuf};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Configuration of a `DtlsConnector`, deserialized with the `serde` feature.
+///
+/// Unknown keys are rejected. Keys which are left out keep the defaults of
+/// `DtlsConnector::builder`. Paths are resolved relative to the working directory.
+///
+/// ```toml
+/// min_protocol = "dtls1.2"
+/// srtp_profiles = ["SRTP_AES128_CM_SHA1_80"]
+/// ciphers = ["ECDHE-ECDSA-AES128-GCM-SHA256"]
+/// root_certificates = ["/etc/dtls/root-ca.pem"]
+/// use_sni = true
+///
+/// [identity]
+/// pkcs12 = "/etc/dtls/client.p12"
+/// password = { env = "DTLS_CLIENT_PASSWORD" }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct ConnectorConfig {
+    /// The minimum supported protocol version, see `DtlsConnectorBuilder::min_protocol_version`.
+    pub min_protocol: Option<Protocol>,
+    /// The maximum supported protocol version, see `DtlsConnectorBuilder::max_protocol_version`.
+    pub max_protocol: Option<Protocol>,
+    /// The SRTP profiles to offer, see `DtlsConnectorBuilder::add_srtp_profile`.
+    #[serde(default)]
+    pub srtp_profiles: Vec<SrtpProfile>,
+    /// The allowed ciphers, see `DtlsConnectorBuilder::add_cipher`.
+    #[serde(default)]
+    pub ciphers: Vec<String>,
+    /// PEM or DER files with certificates to trust, see
+    /// `DtlsConnectorBuilder::add_root_certificate`.
+    #[serde(default)]
+    pub root_certificates: Vec<PathBuf>,
+    /// The identity for client certificate authentication.
+    pub identity: Option<IdentityConfig>,
+    /// See `DtlsConnectorBuilder::use_sni`.
+    pub use_sni: Option<bool>,
+    /// See `DtlsConnectorBuilder::danger_accept_invalid_hostnames`.
+    pub danger_accept_invalid_hostnames: Option<bool>,
+    /// See `DtlsConnectorBuilder::danger_accept_invalid_certs`.
+    pub danger_accept_invalid_certs: Option<bool>,
+}
+
+impl ConnectorConfig {
+    /// Returns a builder with the settings from this configuration.
+    ///
+    /// Reads the certificate and identity files. Fails with `Error::Configuration` naming the
+    /// offending key if a file can not be read or parsed.
+    pub fn builder(&self) -> Result<DtlsConnectorBuilder> {
+        let mut builder = DtlsConnector::builder();
+
+        if let Some(protocol) = self.min_protocol {
+            builder.min_protocol_version(Some(protocol));
+        }
+        if let Some(protocol) = self.max_protocol {
+            builder.max_protocol_version(Some(protocol));
+        }
+        for profile in &self.srtp_profiles {
+            builder.add_srtp_profile(*profile);
+        }
+        for cipher in &self.ciphers {
+            builder.add_cipher(cipher.as_str());
+        }
+        for (i, path) in self.root_certificates.iter().enumerate() {
+            let certificate = read_certificate(path)
+                .map_err(|e| invalid(&format!("root_certificates[{}]", i), path, e))?;
+            builder.add_root_certificate(certificate);
+        }
+        if let Some(ref identity) = self.identity {
+            let identity = identity.load("identity")?;
+            builder.identity(ConnectorIdentity::Certificate(identity));
+        }
+        if let Some(use_sni) = self.use_sni {
+            builder.use_sni(use_sni);
+        }
+        if let Some(accept) = self.danger_accept_invalid_hostnames {
+            builder.danger_accept_invalid_hostnames(accept);
+        }
+        if let Some(accept) = self.danger_accept_invalid_certs {
+            builder.danger_accept_invalid_certs(accept);
+        }
+
+        Ok(builder)
+    }
+}
+
+/// Configuration of a `DtlsAcceptor`, deserialized with the `serde` feature.
+///
+/// Unknown keys are rejected. Keys which are left out keep the defaults of
+/// `DtlsAcceptor::builder`. Paths are resolved relative to the working directory.
+///
+/// ```toml
+/// min_protocol = "dtls1.2"
+/// srtp_profiles = ["SRTP_AES128_CM_SHA1_80"]
+///
+/// [identity]
+/// pkcs12 = "/etc/dtls/server.p12"
+/// password = { file = "/run/secrets/dtls-server-password" }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct AcceptorConfig {
+    /// The minimum supported protocol version, see `DtlsAcceptorBuilder::min_protocol_version`.
+    pub min_protocol: Option<Protocol>,
+    /// The maximum supported protocol version, see `DtlsAcceptorBuilder::max_protocol_version`.
+    pub max_protocol: Option<Protocol>,
+    /// The SRTP profiles to accept, see `DtlsAcceptorBuilder::add_srtp_profile`.
+    #[serde(default)]
+    pub srtp_profiles: Vec<SrtpProfile>,
+    /// The allowed ciphers, see `DtlsAcceptorBuilder::add_cipher`.
+    #[serde(default)]
+    pub ciphers: Vec<String>,
+    /// The identity of the server.
+    pub identity: IdentityConfig,
+}
+
+impl AcceptorConfig {
+    /// Returns a builder with the settings from this configuration.
+    ///
+    /// Reads the identity file. Fails with `Error::Configuration` naming the offending key if it
+    /// can not be read or parsed.
+    pub fn builder(&self) -> Result<DtlsAcceptorBuilder> {
+        let mut builder = DtlsAcceptor::builder(self.identity.load("identity")?);
+
+        if let Some(protocol) = self.min_protocol {
+            builder.min_protocol_version(Some(protocol));
+        }
+        if let Some(protocol) = self.max_protocol {
+            builder.max_protocol_version(Some(protocol));
+        }
+        for profile in &self.srtp_profiles {
+            builder.add_srtp_profile(*profile);
+        }
+        for cipher in &self.ciphers {
+            builder.add_cipher(cipher.as_str());
+        }
+
+        Ok(builder)
+    }
+}
+
+/// A PKCS #12 archive holding a certificate identity, see `CertificateIdentity::from_pkcs12`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct IdentityConfig {
+    /// The path of the DER-formatted archive.
+    pub pkcs12: PathBuf,
+    /// Where the password decrypting the archive is read from.
+    pub password: PasswordSource,
+}
+
+impl IdentityConfig {
+    fn load(&self, key: &str) -> Result<CertificateIdentity> {
+        let password = self
+            .password
+            .read()
+            .map_err(|e| Error::Configuration(format!("{}.password: {}", key, e)))?;
+        let archive = fs::read(&self.pkcs12)
+            .map_err(Error::Io)
+            .and_then(|archive| CertificateIdentity::from_pkcs12(&archive, &password))
+            .map_err(|e| invalid(&format!("{}.pkcs12", key), &self.pkcs12, e))?;
+        Ok(archive)
+    }
+}
+
+/// The source of a password, written as a table with a single key in TOML, e.g.
+/// `password = { env = "DTLS_PASSWORD" }`.
+///
+/// Passwords are wiped from memory when dropped.
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+#[non_exhaustive]
+pub enum PasswordSource {
+    /// The password itself.
+    Value(String),
+    /// The name of an environment variable holding the password.
+    Env(String),
+    /// The path of a file holding the password, a trailing line break is ignored.
+    File(PathBuf),
+}
+
+impl PasswordSource {
+    fn read(&self) -> std::result::Result<Zeroizing<String>, String> {
+        match self {
+            PasswordSource::Value(password) => Ok(Zeroizing::new(password.clone())),
+            PasswordSource::Env(name) => env::var(name)
+                .map(Zeroizing::new)
+                .map_err(|e| format!("environment variable `{}`: {}", name, e)),
+            PasswordSource::File(path) => fs::read_to_string(path)
+                .map(|password| {
+                    let mut password = Zeroizing::new(password);
+                    let len = password.trim_end_matches(&['\r', '\n'][..]).len();
+                    password.truncate(len);
+                    password
+                })
+                .map_err(|e| format!("{}: {}", path.display(), e)),
+        }
+    }
+}
+
+impl Drop for PasswordSource {
+    fn drop(&mut self) {
+        if let PasswordSource::Value(password) = self {
+            password.zeroize();
+        }
+    }
+}
+
+/// Never prints the password itself.
+impl fmt::Debug for PasswordSource {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PasswordSource::Value(_) => fmt.write_str("Value(<redacted>)"),
+            PasswordSource::Env(name) => fmt.debug_tuple("Env").field(name).finish(),
+            PasswordSource::File(path) => fmt.debug_tuple("File").field(path).finish(),
+        }
+    }
+}
+
+/// Reads a PEM or DER encoded certificate.
+fn read_certificate(path: &Path) -> Result<Certificate> {
+    let buf = fs::read(path).map_err(Error::Io)?;
+    if buf.starts_with(b"-----BEGIN") {
+        Certificate::from_pem(&buf)
+    } else {
+        Certificate::from_der(&buf)
+    }
+}
+
+fn invalid(key: &str, path: &Path, error: Error) -> Error {
+    Error::Configuration(format!("{}: {}: {}", key, path.display(), error))
+}
@@ -0,0 +1,15 @@
+/// Which certificates of the peer's chain are checked against the loaded certificate revocation
+/// lists.
+///
+/// A certificate fails verification if no CRL of its issuer was loaded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CrlCheck {
+    /// Only the peer's own certificate is checked.
+    ///
+    /// This corresponds to `X509_V_FLAG_CRL_CHECK`.
+    Leaf,
+    /// Every certificate of the chain is checked.
+    ///
+    /// This corresponds to `X509_V_FLAG_CRL_CHECK` and `X509_V_FLAG_CRL_CHECK_ALL`.
+    Chain,
+}
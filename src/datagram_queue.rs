@@ -0,0 +1,60 @@
+use crate::{ReadTimeout, TryCloneSocket};
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+};
+
+/// The in-memory transport of a `DtlsSession`, holding the datagrams received from and to be sent
+/// to the peer.
+///
+/// Each read returns one received datagram, and each write queues one datagram, as OpenSSL writes
+/// a whole datagram at a time. Reads fail with `io::ErrorKind::WouldBlock` while nothing was
+/// received.
+#[derive(Debug, Default)]
+pub struct DatagramQueue {
+    incoming: VecDeque<Vec<u8>>,
+    outgoing: VecDeque<Vec<u8>>,
+}
+
+impl DatagramQueue {
+    pub(crate) fn push_incoming(&mut self, datagram: &[u8]) {
+        self.incoming.push_back(datagram.to_vec());
+    }
+
+    pub(crate) fn pop_outgoing(&mut self) -> Option<Vec<u8>> {
+        self.outgoing.pop_front()
+    }
+
+    pub(crate) fn has_outgoing(&self) -> bool {
+        !self.outgoing.is_empty()
+    }
+}
+
+impl Read for DatagramQueue {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let datagram = match self.incoming.pop_front() {
+            Some(datagram) => datagram,
+            None => return Err(io::ErrorKind::WouldBlock.into()),
+        };
+
+        // like a UDP socket, the part of the datagram which does not fit is discarded
+        let len = datagram.len().min(buf.len());
+        buf[..len].copy_from_slice(&datagram[..len]);
+        Ok(len)
+    }
+}
+
+impl Write for DatagramQueue {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.outgoing.push_back(buf.to_vec());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl ReadTimeout for DatagramQueue {}
+
+impl TryCloneSocket for DatagramQueue {}
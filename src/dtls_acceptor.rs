@@ -1,11 +1,40 @@
-use crate::openssl::try_set_supported_protocols;
-use crate::{DtlsAcceptorBuilder, DtlsStream, HandshakeError, CertificateIdentity, Protocol, Result};
-use openssl::ssl::{SslAcceptor, SslMethod};
-use std::{fmt, io, result};
+use crate::callbacks::{install_alert_callback, install_record_callback};
+use crate::metrics::Session;
+use crate::midhandshake_dtls_steam::handshake_until;
+use crate::trace::Trace;
+use crate::identity::validate_psk_identity;
+use crate::openssl::{
+    allow_client_renegotiation, set_psk_identity_hint, try_set_cipher_lists,
+    try_set_certificate_key_pins, try_set_supported_protocols,
+};
+use crate::keepalive::{install_keepalive_negotiation, Keepalive, KeepaliveSettings};
+use crate::rekey::{Rekey, RekeyLimits};
+use crate::{
+    DtlsAcceptorBuilder, DtlsSession, DtlsStream, HandshakeError, CertificateIdentity, MetricsObserver, Protocol,
+    InvalidRecordPolicy, ReadTimeout, Result, Role,
+};
+use log::debug;
+use openssl::error::ErrorStack;
+use openssl::ssl::{Ssl, SslAcceptor, SslMethod, SslOptions};
+use std::{
+    fmt, io,
+    net::SocketAddr,
+    result,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 /// Acceptor for incoming UDP sessions secured with DTLS.
 #[derive(Clone)]
-pub struct DtlsAcceptor(SslAcceptor);
+pub struct DtlsAcceptor {
+    acceptor: SslAcceptor,
+    handshake_timeout: Option<Duration>,
+    metrics: Option<Arc<dyn MetricsObserver>>,
+    trace_handshake: bool,
+    rekey: RekeyLimits,
+    invalid_records: InvalidRecordPolicy,
+    keepalive: KeepaliveSettings,
+}
 
 impl DtlsAcceptor {
     /// Creates a `DtlsAcceptor` with default settings.
@@ -24,6 +53,17 @@ impl DtlsAcceptor {
     /// - Sets srtp profile by enabling the DTLS extension 'use_srtp'
     /// - Sets the certificate and private key
     /// - Adds the certificates from the identity chain to the certificate chain.
+    /// - Sets the cipher, group and signature algorithm lists, on top of the Mozilla intermediate defaults
+    /// - Sets the identities accepted for PSK authentication and the identity hint
+    /// - Sets the handshake timeout
+    /// - Installs the key log callback
+    /// - Sets the metrics observer
+    /// - Enables tracing of the handshake
+    /// - Refuses or allows client renegotiation and sets the automatic rekeying limits
+    /// - Sets the keepalive interval and offers keepalive to the peer
+    /// - Sets the policy for invalid records
+    /// - Staples the OCSP response
+    /// - Pins the clients' public keys
     pub fn new(builder: &DtlsAcceptorBuilder) -> Result<DtlsAcceptor> {
         let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::dtls())?;
 
@@ -51,7 +91,73 @@ impl DtlsAcceptor {
 
         try_set_supported_protocols(builder.min_protocol, builder.max_protocol, &mut acceptor)?;
 
-        Ok(DtlsAcceptor(acceptor.build()))
+        try_set_cipher_lists(
+            &builder.cipher_list,
+            &builder.groups_list,
+            &builder.sigalgs_list,
+            &mut acceptor,
+        )?;
+
+        if !builder.psk_identities.is_empty() {
+            for identity in &builder.psk_identities {
+                identity.validate()?;
+            }
+            let identities = builder.psk_identities.clone();
+
+            acceptor.set_psk_server_callback(move |_, identity, psk| {
+                let known = identities
+                    .iter()
+                    .find(|known| Some(known.0.as_ref()) == identity);
+
+                match known {
+                    Some(known) if known.1.len() <= psk.len() => {
+                        psk[..known.1.len()].copy_from_slice(&known.1);
+                        Ok(known.1.len())
+                    }
+                    _ => {
+                        debug!("psk_server_callback: unknown identity {:?}", identity);
+                        Ok(0)
+                    }
+                }
+            });
+        }
+
+        if let Some(ref hint) = builder.psk_identity_hint {
+            validate_psk_identity("PSK identity hint", hint)?;
+            set_psk_identity_hint(&mut acceptor, hint)?;
+        }
+
+        if let Some(ref key_log) = builder.key_log {
+            key_log.install(&mut acceptor)?;
+        }
+
+        if builder.refuse_renegotiation {
+            acceptor.set_options(SslOptions::NO_RENEGOTIATION);
+        } else if builder.allow_client_renegotiation {
+            allow_client_renegotiation(&mut acceptor);
+        }
+
+        if let Some(ref staple) = builder.ocsp_staple {
+            staple.install(&identity.cert, identity.chain.as_deref(), &mut acceptor)?;
+        }
+
+        if !builder.pinned_certificate_keys.is_empty() {
+            try_set_certificate_key_pins(&builder.pinned_certificate_keys, &mut acceptor)?;
+        }
+
+        if builder.keepalive.interval.is_some() {
+            install_keepalive_negotiation(&mut acceptor)?;
+        }
+
+        Ok(DtlsAcceptor {
+            acceptor: acceptor.build(),
+            handshake_timeout: builder.handshake_timeout,
+            metrics: builder.metrics.clone(),
+            trace_handshake: builder.trace_handshake,
+            rekey: builder.rekey,
+            invalid_records: builder.invalid_records,
+            keepalive: builder.keepalive,
+        })
     }
 
     /// Returns a new builder for a `DtlsAcceptor`.
@@ -63,6 +169,22 @@ impl DtlsAcceptor {
             srtp_profiles: vec![],
             min_protocol: Some(Protocol::Dtlsv10),
             max_protocol: None,
+            cipher_list: vec![],
+            groups_list: vec![],
+            sigalgs_list: vec![],
+            psk_identities: vec![],
+            psk_identity_hint: None,
+            handshake_timeout: None,
+            key_log: None,
+            metrics: None,
+            trace_handshake: false,
+            refuse_renegotiation: false,
+            allow_client_renegotiation: false,
+            rekey: RekeyLimits::default(),
+            invalid_records: InvalidRecordPolicy::Drop,
+            keepalive: KeepaliveSettings::default(),
+            ocsp_staple: None,
+            pinned_certificate_keys: vec![],
         }
     }
 
@@ -72,6 +194,10 @@ impl DtlsAcceptor {
     /// the handshake, a `HandshakeError::WouldBlock` error will be returned
     /// which can be used to restart the handshake when the socket is ready
     /// again.
+    ///
+    /// If a handshake timeout is configured, it is checked each time the interrupted handshake is
+    /// resumed. Blocking streams are not interrupted, use `accept_with_deadline` to bound their
+    /// handshake.
     pub fn accept<S: fmt::Debug>(
         &self,
         stream: S,
@@ -79,19 +205,103 @@ impl DtlsAcceptor {
     where
         S: io::Read + io::Write,
     {
-        let stream = self.0.accept(stream)?;
-        Ok(DtlsStream::from(stream))
+        self.accept_inner(stream, None)
+    }
+
+    /// Accepts a new client connection from `peer` with the provided stream, as with `accept`.
+    ///
+    /// The address labels the handshake trace, see `DtlsAcceptorBuilder::trace_handshake`.
+    pub fn accept_from<S>(
+        &self,
+        stream: S,
+        peer: SocketAddr,
+    ) -> result::Result<DtlsStream<S>, HandshakeError<S>>
+    where
+        S: io::Read + io::Write + fmt::Debug,
+    {
+        self.accept_inner(stream, Some(peer))
+    }
+
+    fn accept_inner<S>(
+        &self,
+        stream: S,
+        peer: Option<SocketAddr>,
+    ) -> result::Result<DtlsStream<S>, HandshakeError<S>>
+    where
+        S: io::Read + io::Write + fmt::Debug,
+    {
+        let deadline = self.handshake_timeout.map(|timeout| Instant::now() + timeout);
+        match self.ssl(peer)?.accept(stream) {
+            Ok(stream) => Ok(DtlsStream::established(stream)),
+            Err(e) => Err(HandshakeError::from(e).with_deadline(deadline)),
+        }
+    }
+
+    /// Accepts a new client connection, the handshake has to complete before the given deadline.
+    ///
+    /// A blocking stream is driven until the handshake completes, using its read timeout to
+    /// observe the deadline. The read timeout of the stream is restored afterwards.
+    ///
+    /// A nonblocking stream, or a stream without support for read timeouts, returns
+    /// `HandshakeError::WouldBlock` as with `accept` once it has no data, and the deadline is
+    /// checked each time the handshake is resumed.
+    ///
+    /// If the deadline passes, `Error::HandshakeTimeout` is returned and the stream is dropped.
+    pub fn accept_with_deadline<S>(
+        &self,
+        stream: S,
+        deadline: Instant,
+    ) -> result::Result<DtlsStream<S>, HandshakeError<S>>
+    where
+        S: io::Read + io::Write + ReadTimeout + fmt::Debug,
+    {
+        let ssl = self.ssl(None)?;
+        handshake_until(stream, deadline, |stream| ssl.accept(stream))
+    }
+
+    /// Creates a session which does not perform any I/O itself, awaiting the client's handshake.
+    ///
+    /// Pass the client's first datagram to `DtlsSession::handle_datagram`. The handshake timeout
+    /// is not applied, the caller decides when to give up on the handshake.
+    pub fn accept_session(&self) -> Result<DtlsSession> {
+        let mut ssl = self.ssl(None)?;
+        ssl.set_accept_state();
+        DtlsSession::start(ssl)
+    }
+
+    fn ssl(&self, peer: Option<SocketAddr>) -> result::Result<Ssl, ErrorStack> {
+        let mut ssl = Ssl::new(self.acceptor.context())?;
+        install_alert_callback(&mut ssl)?;
+        install_record_callback(&mut ssl, self.invalid_records)?;
+        Rekey::start(self.rekey, &mut ssl)?;
+        Keepalive::start(self.keepalive, &mut ssl)?;
+        if let Some(ref metrics) = self.metrics {
+            Session::start(metrics, Role::Server, &mut ssl)?;
+        }
+        if self.trace_handshake {
+            Trace::start(Role::Server, peer, &mut ssl)?;
+        }
+
+        Ok(ssl)
     }
 }
 
 impl From<SslAcceptor> for DtlsAcceptor {
     fn from(acceptor: SslAcceptor) -> Self {
-        DtlsAcceptor(acceptor)
+        DtlsAcceptor {
+            acceptor,
+            handshake_timeout: None,
+            metrics: None,
+            trace_handshake: false,
+            rekey: RekeyLimits::default(),
+            invalid_records: InvalidRecordPolicy::Drop,
+            keepalive: KeepaliveSettings::default(),
+        }
     }
 }
 
 impl AsRef<SslAcceptor> for DtlsAcceptor {
     fn as_ref(&self) -> &SslAcceptor {
-        &self.0
+        &self.acceptor
     }
 }
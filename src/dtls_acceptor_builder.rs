@@ -1,15 +1,48 @@
-use crate::{DtlsAcceptor, CertificateIdentity, Protocol, Result, SrtpProfile};
+use crate::{
+    CertificateIdentity, CipherPreset, DtlsAcceptor, InvalidRecordPolicy, KeyLog, MetricsObserver,
+    OcspStaple, Protocol, PskIdentity, PublicKey, Result, SrtpProfile,
+};
+use crate::keepalive::KeepaliveSettings;
+use crate::rekey::RekeyLimits;
+use std::{sync::Arc, time::Duration};
 
 /// A builder for `DtlsAcceptor`s.
 /// With this builder you can configure the following DTLS properties:
 /// - The identity to be used for client certificate authentication
 /// - Adding and enabling the the DTLS extension 'use_srtp'
 /// - Configuring min/max supported DTLS versions
+/// - Restricting ciphers, groups and signature algorithms
+/// - Accepting clients authenticating with a pre-shared key, sending them an identity hint
+/// - Limiting the duration of the handshake
+/// - Logging session secrets for debugging
+/// - Observing handshakes and traffic for metrics
+/// - Tracing the handshake
+/// - Refusing renegotiation and rekeying sessions automatically
+/// - Probing idle peers with keepalive records
+/// - Reporting invalid records
+/// - Stapling an OCSP response
+/// - Authenticating clients by pinned public keys
 pub struct DtlsAcceptorBuilder {
     pub(crate) identity: CertificateIdentity,
     pub(crate) srtp_profiles: Vec<SrtpProfile>,
     pub(crate) min_protocol: Option<Protocol>,
     pub(crate) max_protocol: Option<Protocol>,
+    pub(crate) cipher_list: Vec<String>,
+    pub(crate) groups_list: Vec<String>,
+    pub(crate) sigalgs_list: Vec<String>,
+    pub(crate) psk_identities: Vec<PskIdentity>,
+    pub(crate) psk_identity_hint: Option<Vec<u8>>,
+    pub(crate) handshake_timeout: Option<Duration>,
+    pub(crate) key_log: Option<KeyLog>,
+    pub(crate) metrics: Option<Arc<dyn MetricsObserver>>,
+    pub(crate) trace_handshake: bool,
+    pub(crate) refuse_renegotiation: bool,
+    pub(crate) allow_client_renegotiation: bool,
+    pub(crate) rekey: RekeyLimits,
+    pub(crate) invalid_records: InvalidRecordPolicy,
+    pub(crate) keepalive: KeepaliveSettings,
+    pub(crate) ocsp_staple: Option<OcspStaple>,
+    pub(crate) pinned_certificate_keys: Vec<PublicKey>,
 }
 
 impl DtlsAcceptorBuilder {
@@ -53,6 +86,283 @@ impl DtlsAcceptorBuilder {
         self
     }
 
+    /// Adds cipher name to the list of allowed ciphers.
+    ///
+    /// Defaults to the Mozilla intermediate cipher list.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_CTX_set_cipher_list`].
+    ///
+    /// [`SSL_CTX_set_cipher_list`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_cipher_list.html
+    pub fn add_cipher<C: Into<String>>(&mut self, cipher: C) -> &mut DtlsAcceptorBuilder {
+        self.cipher_list.push(cipher.into());
+        self
+    }
+
+    /// Adds a group (elliptic curve) to the list of supported groups, e.g. `X25519` or `P-256`.
+    ///
+    /// Defaults to the OpenSSL defaults.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_CTX_set1_groups_list`].
+    ///
+    /// [`SSL_CTX_set1_groups_list`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set1_groups_list.html
+    pub fn add_group<G: Into<String>>(&mut self, group: G) -> &mut DtlsAcceptorBuilder {
+        self.groups_list.push(group.into());
+        self
+    }
+
+    /// Adds a signature algorithm to the list of supported signature algorithms, e.g. `ECDSA+SHA256`.
+    ///
+    /// Defaults to the OpenSSL defaults.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_CTX_set1_sigalgs_list`].
+    ///
+    /// [`SSL_CTX_set1_sigalgs_list`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set1_sigalgs_list.html
+    pub fn add_signature_algorithm<A: Into<String>>(
+        &mut self,
+        algorithm: A,
+    ) -> &mut DtlsAcceptorBuilder {
+        self.sigalgs_list.push(algorithm.into());
+        self
+    }
+
+    /// Replaces the ciphers, groups and signature algorithms with those of the given preset.
+    ///
+    /// `CipherPreset::Modern` also raises the minimum protocol version to DTLS 1.2.
+    ///
+    /// Ciphers, groups and signature algorithms added afterwards are appended to the preset.
+    pub fn cipher_preset(&mut self, preset: CipherPreset) -> &mut DtlsAcceptorBuilder {
+        preset.apply(
+            &mut self.cipher_list,
+            &mut self.groups_list,
+            &mut self.sigalgs_list,
+            &mut self.min_protocol,
+        );
+        self
+    }
+
+    /// Adds an identity/key pair that clients may use for PSK authentication.
+    ///
+    /// A PSK cipher has to be enabled as well, e.g. with `CipherPreset::PskOnly`. `build` fails if
+    /// the key is longer than 256 bytes, or the identity is longer than 128 bytes or contains a
+    /// NUL byte.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_CTX_set_psk_server_callback`].
+    ///
+    /// [`SSL_CTX_set_psk_server_callback`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_psk_server_callback.html
+    pub fn add_psk_identity(&mut self, identity: PskIdentity) -> &mut DtlsAcceptorBuilder {
+        self.psk_identities.push(identity);
+        self
+    }
+
+    /// Sets the identity hint sent to clients authenticating with a pre-shared key.
+    ///
+    /// Clients may choose their identity and key by the hint, see
+    /// `DtlsConnectorBuilder::add_psk_identity_for_hint`. `build` fails if the hint is longer than
+    /// 128 bytes or contains a NUL byte.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_CTX_use_psk_identity_hint`].
+    ///
+    /// [`SSL_CTX_use_psk_identity_hint`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_use_psk_identity_hint.html
+    pub fn psk_identity_hint(&mut self, hint: &[u8]) -> &mut DtlsAcceptorBuilder {
+        self.psk_identity_hint = Some(hint.to_vec());
+        self
+    }
+
+    /// Sets the time a handshake started by `DtlsAcceptor::accept` may take.
+    ///
+    /// The deadline is checked each time an interrupted handshake is resumed, so it bounds the
+    /// handshakes of nonblocking streams. Blocking streams are bounded by `accept_with_deadline`
+    /// instead.
+    ///
+    /// Defaults to `None`, waiting for the peer forever.
+    pub fn handshake_timeout(&mut self, timeout: Option<Duration>) -> &mut DtlsAcceptorBuilder {
+        self.handshake_timeout = timeout;
+        self
+    }
+
+    /// Logs the session secrets of every connection, so captured traffic can be decrypted.
+    ///
+    /// Only available in debug builds or with the `keylog` cargo feature, `build` fails otherwise.
+    /// A warning is logged when the acceptor is built with key logging enabled.
+    ///
+    /// Defaults to `None`.
+    ///
+    /// # Warning
+    ///
+    /// Anyone with access to the logged secrets can decrypt the sessions, never enable this in
+    /// production.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_CTX_set_keylog_callback`].
+    ///
+    /// [`SSL_CTX_set_keylog_callback`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_keylog_callback.html
+    pub fn key_log(&mut self, key_log: Option<KeyLog>) -> &mut DtlsAcceptorBuilder {
+        self.key_log = key_log;
+        self
+    }
+
+    /// Sets the observer receiving handshake, retransmission and traffic events of every session.
+    ///
+    /// Defaults to `None`.
+    pub fn metrics(
+        &mut self,
+        observer: Option<Arc<dyn MetricsObserver>>,
+    ) -> &mut DtlsAcceptorBuilder {
+        self.metrics = observer;
+        self
+    }
+
+    /// Logs every handshake state change, handshake message, alert and retransmission.
+    ///
+    /// The events are logged at debug level with the `udp_dtls::trace` target, labeled with the
+    /// role and the peer address passed to `accept_from`, if any.
+    ///
+    /// Defaults to `false`.
+    pub fn trace_handshake(&mut self, trace: bool) -> &mut DtlsAcceptorBuilder {
+        self.trace_handshake = trace;
+        self
+    }
+
+    /// Refuses renegotiations initiated by the peer, answering them with a `no_renegotiation`
+    /// alert.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// # Underlying SSL
+    /// This sets `SSL_OP_NO_RENEGOTIATION` with [`SSL_CTX_set_options`].
+    ///
+    /// [`SSL_CTX_set_options`]: https://www.openssl.org/docs/manmaster/man3/SSL_CTX_set_options.html
+    pub fn refuse_renegotiation(&mut self, refuse: bool) -> &mut DtlsAcceptorBuilder {
+        self.refuse_renegotiation = refuse;
+        self
+    }
+
+    /// Accepts renegotiations initiated by the client.
+    ///
+    /// OpenSSL 3.0 and newer refuse them by default, as each renegotiation costs the server a
+    /// full handshake. Older versions accept them regardless of this setting. Clients rekeying
+    /// with `DtlsStream::renegotiate`, `rekey_after_bytes` or `rekey_after_records` need this
+    /// on the acceptor. Has no effect when `refuse_renegotiation` is set.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// # Underlying SSL
+    /// This sets `SSL_OP_ALLOW_CLIENT_RENEGOTIATION` with [`SSL_CTX_set_options`].
+    ///
+    /// [`SSL_CTX_set_options`]: https://www.openssl.org/docs/manmaster/man3/SSL_CTX_set_options.html
+    pub fn allow_client_renegotiation(&mut self, allow: bool) -> &mut DtlsAcceptorBuilder {
+        self.allow_client_renegotiation = allow;
+        self
+    }
+
+    /// Renegotiates a session after `bytes` bytes of application data were sent and received
+    /// since the handshake or the last renegotiation.
+    ///
+    /// The renegotiation is started by the next read or write on the `DtlsStream`, see
+    /// `DtlsStream::renegotiate`. Only enable automatic rekeying on one side of a session.
+    ///
+    /// Defaults to `None`.
+    pub fn rekey_after_bytes(&mut self, bytes: Option<u64>) -> &mut DtlsAcceptorBuilder {
+        self.rekey.bytes = bytes;
+        self
+    }
+
+    /// Renegotiates a session after `records` records of application data were sent and received
+    /// since the handshake or the last renegotiation.
+    ///
+    /// The renegotiation is started by the next read or write on the `DtlsStream`, see
+    /// `DtlsStream::renegotiate`. Only enable automatic rekeying on one side of a session.
+    ///
+    /// Defaults to `None`.
+    pub fn rekey_after_records(&mut self, records: Option<u64>) -> &mut DtlsAcceptorBuilder {
+        self.rekey.records = records;
+        self
+    }
+
+    /// Probes the peer after `interval` without receiving a record, and considers it dead after
+    /// the number of missed probes set with `keepalive_max_missed`.
+    ///
+    /// Probes are sent by `DtlsStream::handle_timeout`, which event loops call once
+    /// `DtlsStream::timeout` elapsed, e.g. after a read timed out. Once the peer is dead,
+    /// `handle_timeout` fails with `Error::KeepaliveTimeout` and reads fail with an `io::Error`
+    /// of kind `io::ErrorKind::TimedOut` wrapping it.
+    ///
+    /// Keepalive only works between peers using this crate. OpenSSL dropped the DTLS heartbeat
+    /// extension, so the peers agree on keepalive with a private extension during the handshake,
+    /// and the probes and their answers are short application data records which reads consume.
+    /// A session only probes its peer if both peers enabled keepalive, other peers are never
+    /// probed nor reported dead. In a session where both peers enabled it, the application must
+    /// not send records equal to the probes.
+    ///
+    /// Defaults to `None`.
+    pub fn keepalive_interval(&mut self, interval: Option<Duration>) -> &mut DtlsAcceptorBuilder {
+        self.keepalive.interval = interval;
+        self
+    }
+
+    /// Sets the number of consecutive keepalive probes the peer may leave unanswered.
+    ///
+    /// Defaults to `3`.
+    pub fn keepalive_max_missed(&mut self, probes: u32) -> &mut DtlsAcceptorBuilder {
+        self.keepalive.max_missed = probes;
+        self
+    }
+
+    /// Sets whether reads report records dropped as replayed, unauthentic or out of epoch.
+    ///
+    /// The dropped records are counted in `DtlsStream::record_stats` with either policy. Drops
+    /// are detected as estimated by `RecordStats`.
+    ///
+    /// Defaults to `InvalidRecordPolicy::Drop`.
+    pub fn invalid_record_policy(&mut self, policy: InvalidRecordPolicy) -> &mut DtlsAcceptorBuilder {
+        self.invalid_records = policy;
+        self
+    }
+
+    /// Staples the OCSP response held by `staple` to the handshakes of clients requesting it.
+    ///
+    /// Keep a clone of the `OcspStaple` to refresh the response, see `OcspStaple::set`. Nothing is
+    /// stapled while it holds no response, or a response not covering the certificate of the
+    /// identity. The issuer of the certificate has to be part of the identity's chain, so
+    /// self-signed certificates are never stapled.
+    ///
+    /// Defaults to `None`.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_CTX_set_tlsext_status_cb`] and [`SSL_set_tlsext_status_ocsp_resp`].
+    ///
+    /// [`SSL_CTX_set_tlsext_status_cb`]: https://www.openssl.org/docs/manmaster/man3/SSL_CTX_set_tlsext_status_cb.html
+    /// [`SSL_set_tlsext_status_ocsp_resp`]: https://www.openssl.org/docs/manmaster/man3/SSL_set_tlsext_status_ocsp_resp.html
+    pub fn ocsp_staple(&mut self, staple: Option<OcspStaple>) -> &mut DtlsAcceptorBuilder {
+        self.ocsp_staple = staple;
+        self
+    }
+
+    /// Pins the public key of client X.509 certificates, replacing the validation of their
+    /// certificate chain and validity period.
+    ///
+    /// Once a key is pinned, clients have to send a certificate, which is accepted if and only if
+    /// its public key, compared as DER-encoded SubjectPublicKeyInfo, is one of the pinned keys.
+    /// An expired, self-signed or otherwise untrusted certificate is accepted if it holds a pinned
+    /// key. Other certificates fail verification with `X509_V_ERR_APPLICATION_VERIFICATION`.
+    ///
+    /// This is SubjectPublicKeyInfo pinning of X.509 certificates, not the raw public keys of
+    /// RFC7250, which need OpenSSL 3.2 and are not supported.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_CTX_set_verify`].
+    ///
+    /// [`SSL_CTX_set_verify`]: https://www.openssl.org/docs/manmaster/man3/SSL_CTX_set_verify.html
+    pub fn pin_certificate_key(&mut self, key: PublicKey) -> &mut DtlsAcceptorBuilder {
+        self.pinned_certificate_keys.push(key);
+        self
+    }
+
     /// Creates a new `DtlsAcceptor` with the settings from this builder.
     pub fn build(&self) -> Result<DtlsAcceptor> {
         Ok(DtlsAcceptor::new(self)?)
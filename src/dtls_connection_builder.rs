@@ -1,24 +1,64 @@
-use crate::{Certificate, DtlsConnector, ConnectorIdentity, Protocol, Result, SrtpProfile};
+use crate::{
+    Certificate, CipherPreset, ConnectorIdentity, CrlCheck, DtlsConnector, InvalidRecordPolicy,
+    KeyLog, MetricsObserver, OcspPolicy, Protocol, PskIdentity, PublicKey, Result, SrtpProfile,
+};
+use crate::keepalive::KeepaliveSettings;
+use crate::rekey::RekeyLimits;
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+pub(crate) type PskIdentityCallback =
+    Arc<dyn Fn(Option<&[u8]>) -> Option<PskIdentity> + Send + Sync>;
  
 /// A builder for `DtlsConnector`s.
 ///
 /// With this builder you can configure the following DTLS properties:
 /// - The identity to be used for client certificate authentication
+/// - Choosing the pre-shared key by the server's identity hint
 /// - Adding and enabling the the DTLS extension 'use_srtp'
 /// - Configuring min/max supported DTLS versions
 /// - Adding a certificate to the set of roots that the connector will trust
+/// - Disabling the system's trust roots, loading CA bundles and certificate revocation lists
 /// - Allowing invalid hostnames/certs for the connection
 /// - Enabling Server Name Indication (SNI)
+/// - Restricting ciphers, groups and signature algorithms
+/// - Limiting the duration of the handshake
+/// - Logging session secrets for debugging
+/// - Observing handshakes and traffic for metrics
+/// - Tracing the handshake
+/// - Refusing renegotiation and rekeying sessions automatically
+/// - Probing idle peers with keepalive records
+/// - Reporting invalid records
+/// - Requesting and validating stapled OCSP responses
+/// - Authenticating the server by a pinned public key
 pub struct DtlsConnectorBuilder {
     pub(crate) identity: Option<ConnectorIdentity>,
+    pub(crate) psk_identities: Vec<(Vec<u8>, PskIdentity)>,
+    pub(crate) psk_identity_callback: Option<PskIdentityCallback>,
     pub(crate) srtp_profiles: Vec<SrtpProfile>,
     pub(crate) min_protocol: Option<Protocol>,
     pub(crate) max_protocol: Option<Protocol>,
     pub(crate) root_certificates: Vec<Certificate>,
+    pub(crate) use_system_roots: bool,
+    pub(crate) root_certificate_files: Vec<PathBuf>,
+    pub(crate) root_certificate_dirs: Vec<PathBuf>,
+    pub(crate) crl_files: Vec<PathBuf>,
+    pub(crate) crl_check: Option<CrlCheck>,
     pub(crate) accept_invalid_certs: bool,
     pub(crate) accept_invalid_hostnames: bool,
     pub(crate) use_sni: bool,
     pub(crate) cipher_list: Vec<String>,
+    pub(crate) groups_list: Vec<String>,
+    pub(crate) sigalgs_list: Vec<String>,
+    pub(crate) handshake_timeout: Option<Duration>,
+    pub(crate) key_log: Option<KeyLog>,
+    pub(crate) metrics: Option<Arc<dyn MetricsObserver>>,
+    pub(crate) trace_handshake: bool,
+    pub(crate) refuse_renegotiation: bool,
+    pub(crate) rekey: RekeyLimits,
+    pub(crate) invalid_records: InvalidRecordPolicy,
+    pub(crate) keepalive: KeepaliveSettings,
+    pub(crate) ocsp_policy: Option<OcspPolicy>,
+    pub(crate) pinned_certificate_keys: Vec<PublicKey>,
 }
 
 impl DtlsConnectorBuilder {
@@ -28,6 +68,43 @@ impl DtlsConnectorBuilder {
         self
     }
 
+    /// Adds a PSK identity/key pair to use if the server sends `hint` as its identity hint.
+    ///
+    /// Servers sending another hint, or none, are answered with the `ConnectorIdentity::Psk` set
+    /// with `identity`, if any. `build` fails if the key is longer than 256 bytes, or the identity
+    /// is longer than 128 bytes or contains a NUL byte.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_CTX_set_psk_client_callback`].
+    ///
+    /// [`SSL_CTX_set_psk_client_callback`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_psk_client_callback.html
+    pub fn add_psk_identity_for_hint(
+        &mut self,
+        hint: &[u8],
+        identity: PskIdentity,
+    ) -> &mut DtlsConnectorBuilder {
+        self.psk_identities.push((hint.to_vec(), identity));
+        self
+    }
+
+    /// Sets a callback choosing the PSK identity/key pair from the server's identity hint.
+    ///
+    /// The callback is asked first; if it returns `None`, the identities added with
+    /// `add_psk_identity_for_hint` and `identity` are used. Identities it returns that OpenSSL can
+    /// not use fail the handshake.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_CTX_set_psk_client_callback`].
+    ///
+    /// [`SSL_CTX_set_psk_client_callback`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_psk_client_callback.html
+    pub fn psk_identity_callback<F>(&mut self, callback: F) -> &mut DtlsConnectorBuilder
+    where
+        F: Fn(Option<&[u8]>) -> Option<PskIdentity> + Send + Sync + 'static,
+    {
+        self.psk_identity_callback = Some(Arc::new(callback));
+        self
+    }
+
     /// Sets the minimum supported protocol version.
     ///
     /// A value of `None` enables support for the oldest protocols supported by the implementation.
@@ -80,7 +157,7 @@ impl DtlsConnectorBuilder {
     /// The connector will use the system's trust root by default. This method can be used to add
     /// to that set when communicating with servers not trusted by the system.
     ///
-    /// Defaults to an empty set.
+    /// Defaults to an empty set. `build` fails if the certificate can not be added.
     ///
     /// # Underlying SSL
     /// This will add a certificate to the certificate store. [`X509_STORE_add_cert`].
@@ -91,6 +168,82 @@ impl DtlsConnectorBuilder {
         self
     }
 
+    /// Controls whether the system's trust roots are trusted.
+    ///
+    /// When disabled, only the certificates added with `add_root_certificate`,
+    /// `add_root_certificates_file` and `add_root_certificates_dir` are trusted.
+    ///
+    /// Defaults to `true`.
+    ///
+    /// # Underlying SSL
+    /// When enabled, this corresponds to [`SSL_CTX_set_default_verify_paths`].
+    ///
+    /// [`SSL_CTX_set_default_verify_paths`]: https://www.openssl.org/docs/manmaster/man3/SSL_CTX_set_default_verify_paths.html
+    pub fn use_system_roots(&mut self, use_system_roots: bool) -> &mut DtlsConnectorBuilder {
+        self.use_system_roots = use_system_roots;
+        self
+    }
+
+    /// Trusts the certificates of a PEM encoded CA bundle file.
+    ///
+    /// `build` fails if the file can not be loaded.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`X509_load_cert_file`].
+    ///
+    /// [`X509_load_cert_file`]: https://www.openssl.org/docs/manmaster/man3/X509_load_cert_file.html
+    pub fn add_root_certificates_file<P: Into<PathBuf>>(
+        &mut self,
+        path: P,
+    ) -> &mut DtlsConnectorBuilder {
+        self.root_certificate_files.push(path.into());
+        self
+    }
+
+    /// Trusts the PEM encoded certificates of a directory, named after their subject name hash as
+    /// created by `openssl rehash`.
+    ///
+    /// The certificates are looked up when a peer is verified.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`X509_LOOKUP_add_dir`].
+    ///
+    /// [`X509_LOOKUP_add_dir`]: https://www.openssl.org/docs/manmaster/man3/X509_LOOKUP_add_dir.html
+    pub fn add_root_certificates_dir<P: Into<PathBuf>>(
+        &mut self,
+        path: P,
+    ) -> &mut DtlsConnectorBuilder {
+        self.root_certificate_dirs.push(path.into());
+        self
+    }
+
+    /// Loads the certificate revocation lists of a PEM encoded file.
+    ///
+    /// The lists are only checked if enabled with `crl_check`. `build` fails if the file can not
+    /// be loaded.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`X509_load_crl_file`].
+    ///
+    /// [`X509_load_crl_file`]: https://www.openssl.org/docs/manmaster/man3/X509_load_crl_file.html
+    pub fn add_crl_file<P: Into<PathBuf>>(&mut self, path: P) -> &mut DtlsConnectorBuilder {
+        self.crl_files.push(path.into());
+        self
+    }
+
+    /// Enables checking the peer's certificates against the loaded certificate revocation lists.
+    ///
+    /// Defaults to `None`.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`X509_STORE_set_flags`].
+    ///
+    /// [`X509_STORE_set_flags`]: https://www.openssl.org/docs/manmaster/man3/X509_STORE_set_flags.html
+    pub fn crl_check(&mut self, check: Option<CrlCheck>) -> &mut DtlsConnectorBuilder {
+        self.crl_check = check;
+        self
+    }
+
     /// Controls the use of certificate validation.
     ///
     /// Defaults to `false`.
@@ -134,11 +287,234 @@ impl DtlsConnectorBuilder {
     }
 
     /// Adds cipher name to the list of allowed ciphers.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_CTX_set_cipher_list`].
+    ///
+    /// [`SSL_CTX_set_cipher_list`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_cipher_list.html
     pub fn add_cipher<C: Into<String>>(&mut self, cipher: C) -> &mut DtlsConnectorBuilder {
         self.cipher_list.push(cipher.into());
         self
     }
 
+    /// Adds a group (elliptic curve) to the list of supported groups, e.g. `X25519` or `P-256`.
+    ///
+    /// Defaults to the OpenSSL defaults.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_CTX_set1_groups_list`].
+    ///
+    /// [`SSL_CTX_set1_groups_list`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set1_groups_list.html
+    pub fn add_group<G: Into<String>>(&mut self, group: G) -> &mut DtlsConnectorBuilder {
+        self.groups_list.push(group.into());
+        self
+    }
+
+    /// Adds a signature algorithm to the list of supported signature algorithms, e.g. `ECDSA+SHA256`.
+    ///
+    /// Defaults to the OpenSSL defaults.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_CTX_set1_sigalgs_list`].
+    ///
+    /// [`SSL_CTX_set1_sigalgs_list`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set1_sigalgs_list.html
+    pub fn add_signature_algorithm<A: Into<String>>(
+        &mut self,
+        algorithm: A,
+    ) -> &mut DtlsConnectorBuilder {
+        self.sigalgs_list.push(algorithm.into());
+        self
+    }
+
+    /// Replaces the ciphers, groups and signature algorithms with those of the given preset.
+    ///
+    /// `CipherPreset::Modern` also raises the minimum protocol version to DTLS 1.2.
+    ///
+    /// Ciphers, groups and signature algorithms added afterwards are appended to the preset.
+    pub fn cipher_preset(&mut self, preset: CipherPreset) -> &mut DtlsConnectorBuilder {
+        preset.apply(
+            &mut self.cipher_list,
+            &mut self.groups_list,
+            &mut self.sigalgs_list,
+            &mut self.min_protocol,
+        );
+        self
+    }
+
+    /// Sets the time a handshake started by `DtlsConnector::connect` may take.
+    ///
+    /// The deadline is checked each time an interrupted handshake is resumed, so it bounds the
+    /// handshakes of nonblocking streams. Blocking streams are bounded by `connect_with_deadline`
+    /// instead.
+    ///
+    /// Defaults to `None`, waiting for the peer forever.
+    pub fn handshake_timeout(&mut self, timeout: Option<Duration>) -> &mut DtlsConnectorBuilder {
+        self.handshake_timeout = timeout;
+        self
+    }
+
+    /// Logs the session secrets of every connection, so captured traffic can be decrypted.
+    ///
+    /// Only available in debug builds or with the `keylog` cargo feature, `build` fails otherwise.
+    /// A warning is logged when the connector is built with key logging enabled.
+    ///
+    /// Defaults to `None`.
+    ///
+    /// # Warning
+    ///
+    /// Anyone with access to the logged secrets can decrypt the sessions, never enable this in
+    /// production.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_CTX_set_keylog_callback`].
+    ///
+    /// [`SSL_CTX_set_keylog_callback`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_keylog_callback.html
+    pub fn key_log(&mut self, key_log: Option<KeyLog>) -> &mut DtlsConnectorBuilder {
+        self.key_log = key_log;
+        self
+    }
+
+    /// Sets the observer receiving handshake, retransmission and traffic events of every session.
+    ///
+    /// Defaults to `None`.
+    pub fn metrics(
+        &mut self,
+        observer: Option<Arc<dyn MetricsObserver>>,
+    ) -> &mut DtlsConnectorBuilder {
+        self.metrics = observer;
+        self
+    }
+
+    /// Logs every handshake state change, handshake message, alert and retransmission.
+    ///
+    /// The events are logged at debug level with the `udp_dtls::trace` target, labeled with the
+    /// role and the peer address passed to `connect_to`, if any.
+    ///
+    /// Defaults to `false`.
+    pub fn trace_handshake(&mut self, trace: bool) -> &mut DtlsConnectorBuilder {
+        self.trace_handshake = trace;
+        self
+    }
+
+    /// Refuses renegotiations initiated by the peer, answering them with a `no_renegotiation`
+    /// alert.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// # Underlying SSL
+    /// This sets `SSL_OP_NO_RENEGOTIATION` with [`SSL_CTX_set_options`].
+    ///
+    /// [`SSL_CTX_set_options`]: https://www.openssl.org/docs/manmaster/man3/SSL_CTX_set_options.html
+    pub fn refuse_renegotiation(&mut self, refuse: bool) -> &mut DtlsConnectorBuilder {
+        self.refuse_renegotiation = refuse;
+        self
+    }
+
+    /// Renegotiates a session after `bytes` bytes of application data were sent and received
+    /// since the handshake or the last renegotiation.
+    ///
+    /// The renegotiation is started by the next read or write on the `DtlsStream`, see
+    /// `DtlsStream::renegotiate`. Only enable automatic rekeying on one side of a session.
+    ///
+    /// Defaults to `None`.
+    pub fn rekey_after_bytes(&mut self, bytes: Option<u64>) -> &mut DtlsConnectorBuilder {
+        self.rekey.bytes = bytes;
+        self
+    }
+
+    /// Renegotiates a session after `records` records of application data were sent and received
+    /// since the handshake or the last renegotiation.
+    ///
+    /// The renegotiation is started by the next read or write on the `DtlsStream`, see
+    /// `DtlsStream::renegotiate`. Only enable automatic rekeying on one side of a session.
+    ///
+    /// Defaults to `None`.
+    pub fn rekey_after_records(&mut self, records: Option<u64>) -> &mut DtlsConnectorBuilder {
+        self.rekey.records = records;
+        self
+    }
+
+    /// Probes the peer after `interval` without receiving a record, and considers it dead after
+    /// the number of missed probes set with `keepalive_max_missed`.
+    ///
+    /// Probes are sent by `DtlsStream::handle_timeout`, which event loops call once
+    /// `DtlsStream::timeout` elapsed, e.g. after a read timed out. Once the peer is dead,
+    /// `handle_timeout` fails with `Error::KeepaliveTimeout` and reads fail with an `io::Error`
+    /// of kind `io::ErrorKind::TimedOut` wrapping it.
+    ///
+    /// Keepalive only works between peers using this crate. OpenSSL dropped the DTLS heartbeat
+    /// extension, so the peers agree on keepalive with a private extension during the handshake,
+    /// and the probes and their answers are short application data records which reads consume.
+    /// A session only probes its peer if both peers enabled keepalive, other peers are never
+    /// probed nor reported dead. In a session where both peers enabled it, the application must
+    /// not send records equal to the probes.
+    ///
+    /// Defaults to `None`.
+    pub fn keepalive_interval(&mut self, interval: Option<Duration>) -> &mut DtlsConnectorBuilder {
+        self.keepalive.interval = interval;
+        self
+    }
+
+    /// Sets the number of consecutive keepalive probes the peer may leave unanswered.
+    ///
+    /// Defaults to `3`.
+    pub fn keepalive_max_missed(&mut self, probes: u32) -> &mut DtlsConnectorBuilder {
+        self.keepalive.max_missed = probes;
+        self
+    }
+
+    /// Sets whether reads report records dropped as replayed, unauthentic or out of epoch.
+    ///
+    /// The dropped records are counted in `DtlsStream::record_stats` with either policy. Drops
+    /// are detected as estimated by `RecordStats`.
+    ///
+    /// Defaults to `InvalidRecordPolicy::Drop`.
+    pub fn invalid_record_policy(&mut self, policy: InvalidRecordPolicy) -> &mut DtlsConnectorBuilder {
+        self.invalid_records = policy;
+        self
+    }
+
+    /// Requests an OCSP response stapled by the server and validates it with the given policy.
+    ///
+    /// The response has to be signed by the issuer of the server's certificate or a responder it
+    /// authorized, and has to cover the server's certificate. The result is available with
+    /// `DtlsStream::ocsp_status`.
+    ///
+    /// Defaults to `None`, no response is requested.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_set_tlsext_status_type`] and [`SSL_CTX_set_tlsext_status_cb`].
+    ///
+    /// [`SSL_set_tlsext_status_type`]: https://www.openssl.org/docs/manmaster/man3/SSL_set_tlsext_status_type.html
+    /// [`SSL_CTX_set_tlsext_status_cb`]: https://www.openssl.org/docs/manmaster/man3/SSL_CTX_set_tlsext_status_cb.html
+    pub fn ocsp_stapling(&mut self, policy: Option<OcspPolicy>) -> &mut DtlsConnectorBuilder {
+        self.ocsp_policy = policy;
+        self
+    }
+
+    /// Pins the public key of the server's X.509 certificate, replacing the validation of its
+    /// certificate chain, validity period and hostname.
+    ///
+    /// Once a key is pinned, the server's certificate is accepted if and only if its public key,
+    /// compared as DER-encoded SubjectPublicKeyInfo, is one of the pinned keys. An expired,
+    /// self-signed or otherwise untrusted certificate for another hostname is accepted if it
+    /// holds a pinned key. Other certificates fail verification with
+    /// `X509_V_ERR_APPLICATION_VERIFICATION`.
+    ///
+    /// This is SubjectPublicKeyInfo pinning of X.509 certificates, not the raw public keys of
+    /// RFC7250, which need OpenSSL 3.2 and are not supported.
+    ///
+    /// Pinning has no effect if `danger_accept_invalid_certs` is enabled.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_CTX_set_verify`].
+    ///
+    /// [`SSL_CTX_set_verify`]: https://www.openssl.org/docs/manmaster/man3/SSL_CTX_set_verify.html
+    pub fn pin_certificate_key(&mut self, key: PublicKey) -> &mut DtlsConnectorBuilder {
+        self.pinned_certificate_keys.push(key);
+        self
+    }
+
     /// Creates a new `DtlsConnector` with the settings from this builder.
     pub fn build(&self) -> Result<DtlsConnector> {
         Ok(DtlsConnector::new(self)?)
@@ -1,11 +1,29 @@
 use crate::{
-    openssl::{init_trust, try_set_supported_protocols},
-    DtlsConnectorBuilder, DtlsStream, Error, HandshakeError, Protocol, ConnectorIdentity
+    midhandshake_dtls_steam::handshake_until,
+    openssl::{
+        init_trust, try_set_cipher_lists, try_set_certificate_key_pins, try_set_supported_protocols,
+        try_set_trust,
+    },
+    callbacks::{install_alert_callback, install_record_callback},
+    metrics::Session,
+    ocsp::install_ocsp_validation,
+    keepalive::{install_keepalive_negotiation, Keepalive, KeepaliveSettings},
+    rekey::{Rekey, RekeyLimits},
+    trace::Trace,
+    DtlsConnectorBuilder, DtlsSession, DtlsStream, Error, HandshakeError, MetricsObserver, Protocol, ConnectorIdentity,
+    ErrorKind, InvalidRecordPolicy, OcspPolicy, ReadTimeout, Role, UdpChannel,
 };
 use log::debug;
-use openssl::ssl::{SslConnector, SslMethod, SslVerifyMode};
+use openssl::ssl::{
+    ConnectConfiguration, SslConnector, SslMethod, SslOptions, SslVerifyMode, StatusType,
+};
 use openssl::error::ErrorStack;
-use std::{fmt, io, io::Write};
+use std::{
+    fmt, io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 /// Connector to an UDP endpoint secured with DTLS.
 #[derive(Clone)]
@@ -14,6 +32,13 @@ pub struct DtlsConnector {
     use_sni: bool,
     accept_invalid_hostnames: bool,
     accept_invalid_certs: bool,
+    handshake_timeout: Option<Duration>,
+    metrics: Option<Arc<dyn MetricsObserver>>,
+    trace_handshake: bool,
+    rekey: RekeyLimits,
+    invalid_records: InvalidRecordPolicy,
+    keepalive: KeepaliveSettings,
+    ocsp_policy: Option<OcspPolicy>,
 }
 
 impl DtlsConnector {
@@ -24,10 +49,23 @@ impl DtlsConnector {
     /// The following propperties will be applied from the builder:
     /// - Sets minimal/maximal protocol version
     /// - Sets srtp profile by enabling the DTLS extension 'use_srtp'
-    /// - Sets the certificate and private key
-    /// - Adds the root certificates to the certificate store.
+    /// - Sets the certificate and private key, or the pre-shared keys
+    /// - Sets up the certificate store with the system's roots, root certificates, CA bundles and
+    ///   certificate revocation lists
+    /// - Sets the cipher, group and signature algorithm lists
+    /// - Sets the handshake timeout
+    /// - Installs the key log callback
+    /// - Sets the metrics observer
+    /// - Enables tracing of the handshake
+    /// - Refuses renegotiation and sets the automatic rekeying limits
+    /// - Sets the keepalive interval and offers keepalive to the peer
+    /// - Sets the policy for invalid records
+    /// - Requests and validates stapled OCSP responses
+    /// - Pins the server's public keys
     pub fn new(builder: &DtlsConnectorBuilder) -> Result<DtlsConnector, Error> {
-        init_trust();
+        if builder.use_system_roots {
+            init_trust();
+        }
 
         let mut connector = SslConnector::builder(SslMethod::dtls()).unwrap();
 
@@ -54,36 +92,90 @@ impl DtlsConnector {
                         }
                     }
                 },
-                ConnectorIdentity::Psk(identity_) => {
-                    let identity_ = identity_.clone();
+                ConnectorIdentity::Psk(_) => {}
+            }
+        }
 
-                    connector.set_psk_client_callback(move |_, _, mut identity, mut psk| {
-                        if let Err(err) = identity.write_all(&identity_.0) {
-                            debug!("psk_client_callback error (identity): {:?}", err);
-                            return Err(ErrorStack::get());
-                        }
+        let default_psk = match builder.identity {
+            Some(ConnectorIdentity::Psk(ref identity)) => Some(identity.clone()),
+            _ => None,
+        };
+        if default_psk.is_some()
+            || !builder.psk_identities.is_empty()
+            || builder.psk_identity_callback.is_some()
+        {
+            let identities = builder.psk_identities.clone();
+            let callback = builder.psk_identity_callback.clone();
+            for identity in default_psk.iter().chain(identities.iter().map(|known| &known.1)) {
+                identity.validate()?;
+            }
 
-                        if let Err(err) =  psk.write_all(&identity_.1) {
-                            debug!("psk_client_callback error (psk): {:?}", err);
-                            return Err(ErrorStack::get());
-                        }
+            connector.set_psk_client_callback(move |_, hint, identity, psk| {
+                let chosen = callback.as_ref().and_then(|callback| callback(hint));
+                let chosen = chosen
+                    .as_ref()
+                    .or_else(|| {
+                        identities
+                            .iter()
+                            .find(|known| Some(known.0.as_ref()) == hint)
+                            .map(|known| &known.1)
+                    })
+                    .or(default_psk.as_ref());
 
-                        Ok(identity_.1.len())
-                    });
+                match chosen.map(|chosen| chosen.write_to(identity, psk)) {
+                    Some(Ok(len)) => Ok(len),
+                    Some(Err(err)) => {
+                        debug!("psk_client_callback error: {}", err);
+                        Ok(0)
+                    }
+                    None => {
+                        debug!("psk_client_callback: no identity for hint {:?}", hint);
+                        Ok(0)
+                    }
                 }
-            }
+            });
         }
 
-        if !builder.cipher_list.is_empty() {
-            connector.set_cipher_list(&builder.cipher_list.join(":"))?;
-        }
+        try_set_cipher_lists(
+            &builder.cipher_list,
+            &builder.groups_list,
+            &builder.sigalgs_list,
+            &mut connector,
+        )?;
 
         try_set_supported_protocols(builder.min_protocol, builder.max_protocol, &mut connector)?;
 
+        if let Some(ref key_log) = builder.key_log {
+            key_log.install(&mut connector)?;
+        }
+
+        if builder.refuse_renegotiation {
+            connector.set_options(SslOptions::NO_RENEGOTIATION);
+        }
+
+        try_set_trust(
+            builder.use_system_roots,
+            &builder.root_certificate_files,
+            &builder.root_certificate_dirs,
+            &builder.crl_files,
+            builder.crl_check,
+            &mut connector,
+        )?;
+
         for cert in &builder.root_certificates {
-            if let Err(err) = connector.cert_store_mut().add_cert((cert.as_ref()).clone()) {
-                debug!("add_cert error: {:?}", err);
-            }
+            connector.cert_store_mut().add_cert(cert.as_ref().clone())?;
+        }
+
+        if let Some(policy) = builder.ocsp_policy {
+            install_ocsp_validation(policy, &mut connector)?;
+        }
+
+        if !builder.pinned_certificate_keys.is_empty() {
+            try_set_certificate_key_pins(&builder.pinned_certificate_keys, &mut connector)?;
+        }
+
+        if builder.keepalive.interval.is_some() {
+            install_keepalive_negotiation(&mut connector)?;
         }
 
         Ok(DtlsConnector {
@@ -91,6 +183,13 @@ impl DtlsConnector {
             use_sni: builder.use_sni,
             accept_invalid_hostnames: builder.accept_invalid_hostnames,
             accept_invalid_certs: builder.accept_invalid_certs,
+            handshake_timeout: builder.handshake_timeout,
+            metrics: builder.metrics.clone(),
+            trace_handshake: builder.trace_handshake,
+            rekey: builder.rekey,
+            invalid_records: builder.invalid_records,
+            keepalive: builder.keepalive,
+            ocsp_policy: builder.ocsp_policy,
         })
     }
 
@@ -98,14 +197,33 @@ impl DtlsConnector {
     pub fn builder() -> DtlsConnectorBuilder {
         DtlsConnectorBuilder {
             identity: None,
+            psk_identities: vec![],
+            psk_identity_callback: None,
             srtp_profiles: vec![],
             min_protocol: Some(Protocol::Dtlsv10),
             max_protocol: None,
             root_certificates: vec![],
+            use_system_roots: true,
+            root_certificate_files: vec![],
+            root_certificate_dirs: vec![],
+            crl_files: vec![],
+            crl_check: None,
             use_sni: true,
             accept_invalid_certs: false,
             accept_invalid_hostnames: false,
             cipher_list: vec![],
+            groups_list: vec![],
+            sigalgs_list: vec![],
+            handshake_timeout: None,
+            key_log: None,
+            metrics: None,
+            trace_handshake: false,
+            refuse_renegotiation: false,
+            rekey: RekeyLimits::default(),
+            invalid_records: InvalidRecordPolicy::Drop,
+            keepalive: KeepaliveSettings::default(),
+            ocsp_policy: None,
+            pinned_certificate_keys: vec![],
         }
     }
 
@@ -119,6 +237,10 @@ impl DtlsConnector {
     /// which can be used to restart the handshake when the socket is ready
     /// again.
     ///
+    /// If a handshake timeout is configured, it is checked each time the interrupted handshake is
+    /// resumed. Blocking streams are not interrupted, use `connect_with_deadline` to bound their
+    /// handshake.
+    ///
     /// The domain is ignored if both SNI and hostname verification are
     /// disabled.
     pub fn connect<S: fmt::Debug>(
@@ -129,6 +251,158 @@ impl DtlsConnector {
     where
         S: io::Read + io::Write,
     {
+        self.connect_inner(domain, stream, None)
+    }
+
+    /// Initiates a DTLS handshake with the server at `peer` over the provided stream, as with
+    /// `connect`.
+    ///
+    /// The address labels the handshake trace, see `DtlsConnectorBuilder::trace_handshake`.
+    pub fn connect_to<S>(
+        &self,
+        domain: &str,
+        stream: S,
+        peer: SocketAddr,
+    ) -> Result<DtlsStream<S>, HandshakeError<S>>
+    where
+        S: io::Read + io::Write + fmt::Debug,
+    {
+        self.connect_inner(domain, stream, Some(peer))
+    }
+
+    fn connect_inner<S>(
+        &self,
+        domain: &str,
+        stream: S,
+        peer: Option<SocketAddr>,
+    ) -> Result<DtlsStream<S>, HandshakeError<S>>
+    where
+        S: io::Read + io::Write + fmt::Debug,
+    {
+        let deadline = self.handshake_timeout.map(|timeout| Instant::now() + timeout);
+        match self.configure(peer)?.connect(domain, stream) {
+            Ok(stream) => Ok(DtlsStream::established(stream)),
+            Err(e) => Err(HandshakeError::from(e).with_deadline(deadline)),
+        }
+    }
+
+    /// Initiates a DTLS handshake that has to complete before the given deadline.
+    ///
+    /// A blocking stream is driven until the handshake completes, using its read timeout to
+    /// observe the deadline. The read timeout of the stream is restored afterwards.
+    ///
+    /// A nonblocking stream, or a stream without support for read timeouts, returns
+    /// `HandshakeError::WouldBlock` as with `connect` once it has no data, and the deadline is
+    /// checked each time the handshake is resumed.
+    ///
+    /// If the deadline passes, `Error::HandshakeTimeout` is returned and the stream is dropped.
+    pub fn connect_with_deadline<S>(
+        &self,
+        domain: &str,
+        stream: S,
+        deadline: Instant,
+    ) -> Result<DtlsStream<S>, HandshakeError<S>>
+    where
+        S: io::Read + io::Write + ReadTimeout + fmt::Debug,
+    {
+        self.connect_until(domain, stream, None, deadline)
+    }
+
+    fn connect_until<S>(
+        &self,
+        domain: &str,
+        stream: S,
+        peer: Option<SocketAddr>,
+        deadline: Instant,
+    ) -> Result<DtlsStream<S>, HandshakeError<S>>
+    where
+        S: io::Read + io::Write + ReadTimeout + fmt::Debug,
+    {
+        let ssl = self.configure(peer)?;
+        handshake_until(stream, deadline, |stream| ssl.connect(domain, stream))
+    }
+
+    /// Creates a session which does not perform any I/O itself, and starts its handshake.
+    ///
+    /// The first flight of the handshake is available with `DtlsSession::poll_datagram`. The
+    /// domain is used as with `connect`. The handshake timeout is not applied, the caller decides
+    /// when to give up on the handshake.
+    pub fn connect_session(&self, domain: &str) -> Result<DtlsSession, Error> {
+        let mut ssl = self.configure(None)?.into_ssl(domain)?;
+        ssl.set_connect_state();
+        DtlsSession::start(ssl)
+    }
+
+    /// Resolves `addr`, a `"host:port"` string, and connects to the first resolved address which
+    /// completes the handshake, each over a new `UdpChannel`.
+    ///
+    /// For each address, a socket of its family is bound to an ephemeral port. The host is used
+    /// for SNI and certificate hostname validation as with `connect`, an IPv6 address has to be
+    /// enclosed in brackets.
+    ///
+    /// The next address is only tried if the handshake timed out or failed with an I/O error,
+    /// other errors such as a rejected certificate are returned right away. Without a handshake
+    /// timeout, an address which does not answer blocks forever, see
+    /// `DtlsConnectorBuilder::handshake_timeout`.
+    pub fn connect_host(&self, addr: &str) -> Result<DtlsStream<UdpChannel>, Error> {
+        let host = match addr.rsplit_once(':') {
+            Some((host, _)) => host.trim_start_matches('[').trim_end_matches(']'),
+            None => {
+                return Err(Error::Io(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("missing port in address {}", addr),
+                )))
+            }
+        };
+
+        let mut last_error = None;
+        for remote_addr in addr.to_socket_addrs()? {
+            let local_addr: SocketAddr = match remote_addr {
+                SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+                SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+            };
+            let socket = match UdpSocket::bind(local_addr) {
+                Ok(socket) => socket,
+                Err(e) => {
+                    debug!("binding a socket for {} failed: {}", remote_addr, e);
+                    last_error = Some(Error::Io(e));
+                    continue;
+                }
+            };
+
+            let channel = UdpChannel {
+                socket,
+                remote_addr,
+            };
+            let result = match self.handshake_timeout {
+                Some(timeout) => {
+                    self.connect_until(host, channel, Some(remote_addr), Instant::now() + timeout)
+                }
+                None => self.connect_to(host, channel, remote_addr),
+            };
+            let error = match result {
+                Ok(stream) => return Ok(stream),
+                Err(HandshakeError::Failure(e)) => e,
+                Err(HandshakeError::WouldBlock(_)) => Error::Io(io::ErrorKind::WouldBlock.into()),
+            };
+            match error.kind() {
+                ErrorKind::HandshakeTimeout | ErrorKind::Io(_) => {
+                    debug!("connecting to {} failed: {}", remote_addr, error);
+                    last_error = Some(error);
+                }
+                _ => return Err(error),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            Error::Io(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no addresses found for {}", addr),
+            ))
+        }))
+    }
+
+    fn configure(&self, peer: Option<SocketAddr>) -> Result<ConnectConfiguration, ErrorStack> {
         let mut ssl = self
             .connector
             .configure()?
@@ -137,9 +411,21 @@ impl DtlsConnector {
         if self.accept_invalid_certs {
             ssl.set_verify(SslVerifyMode::NONE);
         }
+        if self.ocsp_policy.is_some() {
+            ssl.set_status_type(StatusType::OCSP)?;
+        }
+        install_alert_callback(&mut ssl)?;
+        install_record_callback(&mut ssl, self.invalid_records)?;
+        Rekey::start(self.rekey, &mut ssl)?;
+        Keepalive::start(self.keepalive, &mut ssl)?;
+        if let Some(ref metrics) = self.metrics {
+            Session::start(metrics, Role::Client, &mut ssl)?;
+        }
+        if self.trace_handshake {
+            Trace::start(Role::Client, peer, &mut ssl)?;
+        }
 
-        let stream = ssl.connect(domain, stream)?;
-        Ok(DtlsStream::from(stream))
+        Ok(ssl)
     }
 }
 
@@ -0,0 +1,126 @@
+use crate::{DatagramQueue, DtlsStream, Error};
+use openssl::ssl::{Ssl, SslStream};
+use std::{
+    io::{self, Read, Write},
+    time::Duration,
+};
+
+/// A DTLS session which does not perform any I/O itself.
+///
+/// The caller owns the socket: it passes each datagram received from the peer to
+/// `handle_datagram`, sends the datagrams returned by `poll_datagram` to the peer, and calls
+/// `handle_timeout` once the duration returned by `timeout` elapsed. The handshake progresses
+/// with these calls, `is_handshaking` returns `false` once it completed.
+///
+/// Application data is exchanged with the `io::Read` and `io::Write` implementations, which fail
+/// with `io::ErrorKind::WouldBlock` until the handshake completed or while no data was received.
+/// Each write queues one datagram. Everything else, e.g. alerts, keying material or shutdown, is
+/// available on the underlying `DtlsStream`, see `stream` and `stream_mut`.
+///
+/// Create sessions with `DtlsConnector::connect_session` and `DtlsAcceptor::accept_session`.
+#[derive(Debug)]
+pub struct DtlsSession {
+    stream: DtlsStream<DatagramQueue>,
+    handshaking: bool,
+}
+
+impl DtlsSession {
+    /// Starts the handshake of `ssl`, for which the connect or accept state was set.
+    pub(crate) fn start(ssl: Ssl) -> Result<DtlsSession, Error> {
+        let stream = SslStream::new(ssl, DatagramQueue::default())?;
+        let mut session = DtlsSession {
+            stream: DtlsStream::from(stream),
+            handshaking: true,
+        };
+        session.handshake()?;
+        Ok(session)
+    }
+
+    /// Passes a datagram received from the peer to the session.
+    ///
+    /// While the handshake is in progress, the datagram is processed immediately and the
+    /// handshake fails with an error if the peer's messages are not acceptable. Afterwards, the
+    /// datagram is processed by the next read.
+    pub fn handle_datagram(&mut self, datagram: &[u8]) -> Result<(), Error> {
+        self.stream.get_mut().push_incoming(datagram);
+        self.handshake()
+    }
+
+    /// Returns the next datagram to send to the peer, if any.
+    ///
+    /// Call this until it returns `None` after each call to `handle_datagram`, `handle_timeout`,
+    /// write or read, as each may produce datagrams.
+    pub fn poll_datagram(&mut self) -> Option<Vec<u8>> {
+        self.stream.get_mut().pop_outgoing()
+    }
+
+    /// Returns whether datagrams are waiting to be sent to the peer.
+    pub fn has_datagrams(&self) -> bool {
+        self.stream.get_ref().has_outgoing()
+    }
+
+    /// Returns the time left until the retransmission timer expires or the next keepalive probe
+    /// is due, if either is running.
+    ///
+    /// See `DtlsStream::timeout`.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.stream.timeout()
+    }
+
+    /// Queues the last flight of messages for retransmission if the retransmission timer expired,
+    /// and a keepalive probe if one is due.
+    ///
+    /// Returns whether the timer had expired or a probe was queued. See
+    /// `DtlsStream::handle_timeout`.
+    pub fn handle_timeout(&mut self) -> Result<bool, Error> {
+        self.stream.handle_timeout()
+    }
+
+    /// Returns whether the handshake is still in progress.
+    pub fn is_handshaking(&self) -> bool {
+        self.handshaking
+    }
+
+    /// Returns a shared reference to the underlying stream.
+    pub fn stream(&self) -> &DtlsStream<DatagramQueue> {
+        &self.stream
+    }
+
+    /// Returns a mutable reference to the underlying stream.
+    ///
+    /// Reads and writes on the stream behave like the ones on the session. Do not use its methods
+    /// waiting for the peer, e.g. `shutdown_timeout`, as the peer's datagrams are only passed in
+    /// by `handle_datagram`.
+    pub fn stream_mut(&mut self) -> &mut DtlsStream<DatagramQueue> {
+        &mut self.stream
+    }
+
+    fn handshake(&mut self) -> Result<(), Error> {
+        if self.handshaking {
+            self.handshaking = !self.stream.drive_handshake()?;
+        }
+        Ok(())
+    }
+}
+
+impl Read for DtlsSession {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.handshaking {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        self.stream.read(buf)
+    }
+}
+
+impl Write for DtlsSession {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.handshaking {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
@@ -1,7 +1,28 @@
-use crate::{Certificate, Error, SrtpProfile};
+use crate::alert::Alerts;
+use crate::keepalive::{Keepalive, PING, PONG};
+use crate::metrics::Session;
+use crate::ocsp::OcspStatus;
+use crate::openssl::{
+    dtls_get_timeout, dtls_handle_timeout, has_pending, received_shutdown, renegotiate,
+};
+use crate::records::Records;
+use crate::rekey::Rekey;
+use crate::split::split;
+use crate::{
+    Alert, Certificate, Error, KeyingMaterial, PublicKey, ReadHalf, ReadTimeout, RecordStats,
+    SessionId, ShutdownResult, SrtpProfile, TryCloneSocket, WriteHalf,
+};
+use bytes::{Bytes, BytesMut};
 use openssl::ssl;
 use openssl::ssl::SslStream;
-use std::{fmt, io};
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    time::{Duration, Instant},
+};
+
+/// The largest application message that fits in a single DTLS record.
+pub const MAX_DATAGRAM_SIZE: usize = 16384;
 
 /// A stream managing a DTLS session.
 ///
@@ -9,6 +30,9 @@ use std::{fmt, io};
 /// and both the server and the client are ready for receiving and sending
 /// data. Bytes read from a `DtlsStream` are decrypted from `S` and bytes written
 /// to a `DtlsStream` are encrypted when passing through to `S`.
+///
+/// The `io::Read` and `io::Write` implementations do not preserve message boundaries, use
+/// `send_datagram` and `recv_datagram` to exchange exactly one message per DTLS record.
 pub struct DtlsStream<S>(ssl::SslStream<S>);
 
 impl<S: fmt::Debug> fmt::Debug for DtlsStream<S> {
@@ -20,16 +44,20 @@ impl<S: fmt::Debug> fmt::Debug for DtlsStream<S> {
 impl<S: io::Read + io::Write> DtlsStream<S> {
     /// Export keying material
     ///
+    /// The returned material is wiped from memory when dropped.
+    ///
     /// # Underlying SSL
     /// This corresponds to [`SSL_export_keying_material`].
     ///
     /// [`SSL_export_keying_material`]: https://www.openssl.org/docs/manmaster/man3/SSL_export_keying_material.html
-    pub fn keying_material(&self, len: usize) -> Result<Vec<u8>, Error> {
-        let mut buf = vec![0; len];
-        self.0
-            .ssl()
-            .export_keying_material(&mut buf, "EXTRACTOR-dtls_srtp", None)?;
-        Ok(buf)
+    pub fn keying_material(&self, len: usize) -> Result<KeyingMaterial, Error> {
+        let mut material = KeyingMaterial::zeroed(len);
+        self.0.ssl().export_keying_material(
+            material.as_mut_slice(),
+            "EXTRACTOR-dtls_srtp",
+            None,
+        )?;
+        Ok(material)
     }
 
     /// Gets the SRTP profile selected by handshake.
@@ -47,6 +75,159 @@ impl<S: io::Read + io::Write> DtlsStream<S> {
         }
     }
 
+    /// Sends `buf` as a single DTLS record.
+    ///
+    /// Returns an error of kind `io::ErrorKind::InvalidInput` if `buf` is empty or larger than
+    /// `MAX_DATAGRAM_SIZE`, in which case nothing is sent.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_write`].
+    ///
+    /// [`SSL_write`]: https://www.openssl.org/docs/manmaster/man3/SSL_write.html
+    pub fn send_datagram(&mut self, buf: &[u8]) -> io::Result<()> {
+        if buf.is_empty() || buf.len() > MAX_DATAGRAM_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "datagram of {} bytes, expected 1 to {} bytes",
+                    buf.len(),
+                    MAX_DATAGRAM_SIZE
+                ),
+            ));
+        }
+
+        let written = self.write(buf)?;
+        if written != buf.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::WriteZero,
+                format!("only {} of {} bytes were sent", written, buf.len()),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Receives a single DTLS record into `buf`, returning the number of bytes received.
+    ///
+    /// `Ok(0)` is returned when the peer has closed the session.
+    ///
+    /// If the record does not fit in `buf`, `buf` is filled with the start of the record, the
+    /// remainder is discarded, and an error of kind `io::ErrorKind::InvalidData` is returned.
+    /// A buffer of `MAX_DATAGRAM_SIZE` bytes always fits a record.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_read`].
+    ///
+    /// [`SSL_read`]: https://www.openssl.org/docs/manmaster/man3/SSL_read.html
+    pub fn recv_datagram(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let received = self.read(buf)?;
+
+        let mut discarded = 0;
+        let mut scratch = [0; 1024];
+        while self.0.ssl().pending() > 0 {
+            discarded += self.read(&mut scratch)?;
+        }
+
+        if discarded > 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "datagram of {} bytes truncated to {} bytes",
+                    received + discarded,
+                    received
+                ),
+            ));
+        }
+        Ok(received)
+    }
+
+    /// Sends `record` as a single DTLS record, like `send_datagram`.
+    pub fn send_bytes(&mut self, record: Bytes) -> io::Result<()> {
+        self.send_datagram(&record)
+    }
+
+    /// Receives a single DTLS record into `buf` and returns it as `Bytes`, like `recv_datagram`.
+    ///
+    /// The record is appended to `buf`, after which the contents of `buf` are split off and
+    /// returned. `buf` keeps its remaining capacity, so a buffer reserved once holds the records
+    /// of many calls without copying them. `MAX_DATAGRAM_SIZE` bytes are reserved if less
+    /// capacity remains. Empty `Bytes` are returned when the peer has closed the session.
+    ///
+    /// On error, `buf` is left unchanged.
+    pub fn recv_bytes(&mut self, buf: &mut BytesMut) -> io::Result<Bytes> {
+        let start = buf.len();
+        buf.resize(start + MAX_DATAGRAM_SIZE, 0);
+
+        let result = self.recv_datagram(&mut buf[start..]);
+        buf.truncate(start + *result.as_ref().unwrap_or(&0));
+        result?;
+
+        Ok(buf.take().freeze())
+    }
+
+    /// Returns the time left until the retransmission timer expires or the next keepalive probe
+    /// is due, if either is running.
+    ///
+    /// Event loops should wake up after this duration and call `handle_timeout`.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`DTLSv1_get_timeout`].
+    ///
+    /// [`DTLSv1_get_timeout`]: https://www.openssl.org/docs/manmaster/man3/DTLSv1_get_timeout.html
+    pub fn timeout(&self) -> Option<Duration> {
+        let retransmission = dtls_get_timeout(self.0.ssl());
+        let keepalive = self.keepalive().map(Keepalive::timeout);
+        retransmission.into_iter().chain(keepalive).min()
+    }
+
+    /// Retransmits the last flight of messages if the retransmission timer expired, and sends a
+    /// keepalive probe if one is due.
+    ///
+    /// Returns whether the timer had expired or a probe was sent. Fails with
+    /// `Error::KeepaliveTimeout` once the peer missed the configured number of probes.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`DTLSv1_handle_timeout`].
+    ///
+    /// [`DTLSv1_handle_timeout`]: https://www.openssl.org/docs/manmaster/man3/DTLSv1_handle_timeout.html
+    pub fn handle_timeout(&mut self) -> Result<bool, Error> {
+        let expired = dtls_handle_timeout(self.0.ssl())?;
+
+        let probe = match self.keepalive() {
+            Some(keepalive) => keepalive.expire()?,
+            None => false,
+        };
+        if probe {
+            self.0
+                .ssl_write(PING)
+                .map_err(|e| Error::Ssl(e, self.0.ssl().verify_result()))?;
+        }
+        Ok(expired || probe)
+    }
+
+    /// Returns the identifier passed to the `MetricsObserver` of the connector or acceptor, if one
+    /// is configured.
+    pub fn session_id(&self) -> Option<SessionId> {
+        Session::get(self.0.ssl()).map(Session::id)
+    }
+
+    /// Returns the last alert received from the peer, if any.
+    ///
+    /// A `close_notify` alert means the peer closed the session orderly, reads return `Ok(0)`
+    /// afterwards. After a fatal alert, reads fail with an `io::Error` of kind
+    /// `io::ErrorKind::ConnectionAborted` wrapping the `Alert`.
+    pub fn received_alert(&self) -> Option<Alert> {
+        Alerts::get(self.0.ssl()).and_then(Alerts::received)
+    }
+
+    /// Returns the last alert sent to the peer, if any.
+    ///
+    /// Sending a chosen fatal alert is not supported, as OpenSSL does not expose a way to send an
+    /// alert outside of a handshake. `shutdown` sends a `close_notify` alert, and OpenSSL sends
+    /// fatal alerts itself when the handshake or a record fails.
+    pub fn sent_alert(&self) -> Option<Alert> {
+        Alerts::get(self.0.ssl()).and_then(Alerts::sent)
+    }
+
     /// Returns a shared reference to the underlying stream.
     pub fn get_ref(&self) -> &S {
         self.0.get_ref()
@@ -84,6 +265,23 @@ impl<S: io::Read + io::Write> DtlsStream<S> {
             .map(|c| Certificate::from(c)))
     }
 
+    /// Returns the public key of the peer's certificate, if present.
+    ///
+    /// This is the key compared against the pinned keys, see
+    /// `DtlsConnectorBuilder::pin_certificate_key`.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_get_peer_certificate`] and [`X509_get_pubkey`].
+    ///
+    /// [`SSL_get_peer_certificate`]: https://www.openssl.org/docs/man1.1.0/ssl/SSL_get_peer_certificate.html
+    /// [`X509_get_pubkey`]: https://www.openssl.org/docs/manmaster/man3/X509_get_pubkey.html
+    pub fn peer_public_key(&self) -> Result<Option<PublicKey>, Error> {
+        match self.0.ssl().peer_certificate() {
+            Some(cert) => Ok(Some(PublicKey::from(cert.public_key()?))),
+            None => Ok(None),
+        }
+    }
+
     /// Shuts down the session.
     ///
     /// The shutdown process consists of two steps. The first step sends a close notify message to
@@ -94,30 +292,269 @@ impl<S: io::Read + io::Write> DtlsStream<S> {
     /// session down. In particular, it must be fully shut down if the connection is to be used for
     /// further communication in the future.
     ///
+    /// Call `shutdown` again after `ShutdownResult::Sent` to await the peer's close notify, or use
+    /// `shutdown_timeout` to bound the wait. On a nonblocking stream, the second step fails with
+    /// `io::ErrorKind::WouldBlock` until the close notify arrived.
+    ///
     /// # Underlying SSL
     /// This corresponds to [`SSL_shutdown`].
     ///
     /// [`SSL_shutdown`]: https://www.openssl.org/docs/man1.0.2/ssl/SSL_shutdown.html
-    pub fn shutdown(&mut self) -> io::Result<()> {
+    pub fn shutdown(&mut self) -> io::Result<ShutdownResult> {
         match self.0.shutdown() {
-            Ok(_) => Ok(()),
-            Err(ref e) if e.code() == ssl::ErrorCode::ZERO_RETURN => Ok(()),
-            Err(e) => Err(e
-                .into_io_error()
-                .unwrap_or_else(|e| io::Error::new(io::ErrorKind::Other, e))),
+            Ok(result) => Ok(result.into()),
+            Err(ref e) if e.code() == ssl::ErrorCode::ZERO_RETURN => Ok(ShutdownResult::Received),
+            Err(e) => Err(io_error(e)),
+        }
+    }
+
+    /// Returns whether the peer sent a close notify message.
+    ///
+    /// The peer does not send any data afterwards, `shutdown` completes without waiting.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_get_shutdown`].
+    ///
+    /// [`SSL_get_shutdown`]: https://www.openssl.org/docs/manmaster/man3/SSL_get_shutdown.html
+    pub fn peer_closed(&self) -> bool {
+        received_shutdown(self.0.ssl())
+    }
+
+    /// Renegotiates the session, refreshing its traffic keys.
+    ///
+    /// The handshake is driven until it completes on a blocking stream, so the peer has to read
+    /// from its stream meanwhile. On a nonblocking stream, an error of kind
+    /// `io::ErrorKind::WouldBlock` is returned and the renegotiation continues with the following
+    /// reads and writes.
+    ///
+    /// Renegotiation only exists up to DTLS 1.2. The key updates of DTLS 1.3 replacing it are not
+    /// available, as OpenSSL does not implement DTLS 1.3.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`SSL_renegotiate`] followed by [`SSL_do_handshake`].
+    ///
+    /// [`SSL_renegotiate`]: https://www.openssl.org/docs/manmaster/man3/SSL_renegotiate.html
+    /// [`SSL_do_handshake`]: https://www.openssl.org/docs/manmaster/man3/SSL_do_handshake.html
+    pub fn renegotiate(&mut self) -> io::Result<()> {
+        renegotiate(self.0.ssl()).map_err(io::Error::other)?;
+        self.0.do_handshake().map_err(io_error)
+    }
+
+    /// Returns the statistics of the records dropped by this session.
+    ///
+    /// The counters are estimates, see `RecordStats`.
+    pub fn record_stats(&self) -> RecordStats {
+        Records::get(self.0.ssl()).map_or_else(RecordStats::default, Records::stats)
+    }
+
+    /// Returns the number of renegotiations completed on this session, whether started by
+    /// `renegotiate`, automatic rekeying or the peer.
+    pub fn renegotiations(&self) -> u64 {
+        Rekey::get(self.0.ssl()).map_or(0, Rekey::renegotiations)
+    }
+
+    /// Returns the revocation status of the server's certificate according to the OCSP response
+    /// it stapled.
+    ///
+    /// Returns `None` on the server side, and on the client side if the connector did not request
+    /// stapling, see `DtlsConnectorBuilder::ocsp_stapling`.
+    pub fn ocsp_status(&self) -> Option<OcspStatus> {
+        OcspStatus::get(self.0.ssl())
+    }
+
+    /// Returns whether a read can return data without reading from the underlying stream.
+    pub(crate) fn has_pending(&self) -> bool {
+        has_pending(self.0.ssl())
+    }
+
+    /// Returns the keepalive state once the handshake completed, if both peers enabled keepalive.
+    fn keepalive(&self) -> Option<&Keepalive> {
+        Keepalive::negotiated(self.0.ssl()).filter(|_| self.0.ssl().is_init_finished())
+    }
+
+    /// Consumes a keepalive probe or answer, returning whether `record` was one.
+    ///
+    /// Every record proves the peer alive. Probes are answered right away.
+    fn handle_keepalive(&mut self, record: &[u8]) -> io::Result<bool> {
+        match Keepalive::negotiated(self.0.ssl()) {
+            Some(keepalive) => keepalive.received(),
+            None => return Ok(false),
+        }
+        if self.0.ssl().pending() > 0 {
+            return Ok(false);
+        }
+
+        if record == PING {
+            self.0.ssl_write(PONG).map_err(io_error)?;
+            return Ok(true);
+        }
+        Ok(record == PONG)
+    }
+
+    /// Continues the handshake, returning whether it completed.
+    ///
+    /// Returns `Ok(false)` while the handshake waits for the peer. Reports the completion or
+    /// failure to the metrics observer, if any.
+    pub(crate) fn drive_handshake(&mut self) -> Result<bool, Error> {
+        match self.0.do_handshake() {
+            Ok(()) => {
+                if let Some(session) = Session::get(self.0.ssl()) {
+                    session.completed();
+                }
+                Ok(true)
+            }
+            Err(ref e)
+                if e.code() == ssl::ErrorCode::WANT_READ
+                    && e.io_error()
+                        .is_none_or(|e| e.kind() == io::ErrorKind::WouldBlock) =>
+            {
+                Ok(false)
+            }
+            Err(e) => {
+                let error = Error::Ssl(e, self.0.ssl().verify_result());
+                if let Some(session) = Session::get(self.0.ssl()) {
+                    session.failed(&error);
+                }
+                Err(error)
+            }
+        }
+    }
+
+    /// Wraps a stream which just completed its handshake, and reports the completion to the
+    /// metrics observer, if any.
+    pub(crate) fn established(stream: SslStream<S>) -> Self {
+        if let Some(session) = Session::get(stream.ssl()) {
+            session.completed();
+        }
+        DtlsStream(stream)
+    }
+}
+
+impl<S: io::Read + io::Write + ReadTimeout + TryCloneSocket> DtlsStream<S> {
+    /// Splits the stream into halves receiving and sending on the same session, e.g. from
+    /// different threads.
+    ///
+    /// The halves share the session behind a mutex. A read waits for the peer on a clone of the
+    /// stream's socket without holding the session, see `TryCloneSocket`, so writes are not held
+    /// up by reads. Without a socket, a read releases the session every few milliseconds instead,
+    /// and waiting writers go first. The read timeout of the stream becomes the one of the
+    /// `ReadHalf`.
+    ///
+    /// Streams whose reads can not be bounded, see `ReadTimeout`, hold the session for the whole
+    /// read, so use them nonblocking.
+    pub fn split(self) -> (ReadHalf<S>, WriteHalf<S>) {
+        split(self)
+    }
+}
+
+impl<S: io::Read + io::Write + ReadTimeout> DtlsStream<S> {
+    /// Shuts down the session, waiting up to `timeout` for the peer's close notify message.
+    ///
+    /// Returns `ShutdownResult::Received` if the peer's close notify arrived in time, and
+    /// `ShutdownResult::Sent` otherwise. Application data received while waiting is discarded.
+    /// The read timeout of the underlying stream is restored afterwards.
+    ///
+    /// Fails with `io::ErrorKind::Unsupported` if the stream can not bound its reads, use
+    /// `shutdown` on nonblocking streams.
+    pub fn shutdown_timeout(&mut self, timeout: Duration) -> io::Result<ShutdownResult> {
+        let deadline = Instant::now() + timeout;
+        let previous = self.get_ref().read_timeout()?;
+
+        let result = self.await_close_notify(deadline);
+        self.get_mut().set_read_timeout(previous)?;
+        result
+    }
+
+    fn await_close_notify(&mut self, deadline: Instant) -> io::Result<ShutdownResult> {
+        if self.shutdown()? == ShutdownResult::Received {
+            return Ok(ShutdownResult::Received);
+        }
+
+        // `SSL_shutdown` fails on application data still in flight, read and discard it instead
+        // until the close notify arrives.
+        let mut buf = [0; MAX_DATAGRAM_SIZE];
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::from_secs(0) {
+                return Ok(ShutdownResult::Sent);
+            }
+
+            self.get_mut().set_read_timeout(Some(remaining))?;
+            match self.0.ssl_read(&mut buf) {
+                Ok(_) => {}
+                Err(ref e) if e.code() == ssl::ErrorCode::ZERO_RETURN => {
+                    return Ok(ShutdownResult::Received)
+                }
+                Err(e) => match e.into_io_error() {
+                    Ok(ref e)
+                        if e.kind() == io::ErrorKind::WouldBlock
+                            || e.kind() == io::ErrorKind::TimedOut => {}
+                    Ok(e) => return Err(e),
+                    Err(e) => return Err(io::Error::other(e)),
+                },
+            }
         }
     }
 }
 
 impl<S: io::Read + io::Write> io::Read for DtlsStream<S> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.0.read(buf)
+        if Keepalive::get(self.0.ssl()).is_some_and(Keepalive::is_dead) {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                Error::KeepaliveTimeout,
+            ));
+        }
+        let records = Records::get(self.0.ssl());
+        if let Some(e) = records.and_then(Records::take_error) {
+            return Err(e);
+        }
+
+        let result = loop {
+            let result = self.0.read(buf);
+            if let Some(records) = Records::get(self.0.ssl()) {
+                records.read_done(matches!(result, Ok(read) if read > 0));
+            }
+            match result {
+                Ok(read) if read > 0 && self.handle_keepalive(&buf[..read])? => {}
+                result => break result,
+            }
+        };
+
+        let read = match result {
+            Ok(read) => read,
+            Err(e) => match self.received_alert() {
+                Some(alert) if alert.is_fatal() => {
+                    return Err(io::Error::new(io::ErrorKind::ConnectionAborted, alert))
+                }
+                _ => {
+                    let records = Records::get(self.0.ssl());
+                    return Err(records.and_then(Records::take_error).unwrap_or(e));
+                }
+            },
+        };
+        if let Some(session) = Session::get(self.0.ssl()) {
+            session.received(read);
+        }
+        if let Some(rekey) = Rekey::get(self.0.ssl()) {
+            if read > 0 && self.0.ssl().pending() == 0 {
+                rekey.record(self.0.ssl(), read);
+            }
+        }
+        Ok(read)
     }
 }
 
 impl<S: io::Read + io::Write> io::Write for DtlsStream<S> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.0.write(buf)
+        let written = self.0.write(buf)?;
+        if let Some(session) = Session::get(self.0.ssl()) {
+            session.sent(written);
+        }
+        if let Some(rekey) = Rekey::get(self.0.ssl()) {
+            rekey.record(self.0.ssl(), written);
+        }
+        Ok(written)
     }
 
     fn flush(&mut self) -> io::Result<()> {
@@ -136,3 +573,16 @@ impl<S: io::Read + io::Write> From<SslStream<S>> for DtlsStream<S> {
         DtlsStream(stream)
     }
 }
+
+/// Converts an OpenSSL error to an `io::Error`, mapping a stalled nonblocking operation to
+/// `io::ErrorKind::WouldBlock`.
+fn io_error(e: ssl::Error) -> io::Error {
+    let want = e.code() == ssl::ErrorCode::WANT_READ || e.code() == ssl::ErrorCode::WANT_WRITE;
+    e.into_io_error().unwrap_or_else(|e| {
+        if want {
+            io::Error::new(io::ErrorKind::WouldBlock, e)
+        } else {
+            io::Error::other(e)
+        }
+    })
+}
@@ -1,6 +1,7 @@
+use crate::metrics::Session;
 use crate::{MidHandshakeDtlsStream, SrtpProfile};
 use openssl::{error::ErrorStack, ssl, x509::X509VerifyResult};
-use std::{error, fmt, result, str::FromStr};
+use std::{error, fmt, io, result, str::FromStr};
 
 /// A typedef of the result-type returned by many methods.
 pub type Result<T> = result::Result<T, Error>;
@@ -16,6 +17,147 @@ pub enum Error {
     Ssl(ssl::Error, X509VerifyResult),
     /// Bad SRTP profile
     SrtpProfile(SrtpProfileError),
+    /// The handshake did not complete before its deadline.
+    HandshakeTimeout,
+    /// The peer did not answer the keepalive probes, see `DtlsConnectorBuilder::keepalive_interval`.
+    KeepaliveTimeout,
+    /// An I/O error of the underlying stream that occurred outside of OpenSSL.
+    Io(io::Error),
+    /// The builder configuration is invalid, contains a description of the problem.
+    Configuration(String),
+}
+
+impl Error {
+    /// Classifies this error, so callers can decide how to react without inspecting OpenSSL's
+    /// error strings.
+    pub fn kind(&self) -> ErrorKind {
+        match *self {
+            Error::Normal(ref e) => classify_error_stack(e).unwrap_or(ErrorKind::Configuration),
+            Error::Ssl(_, v) if v.as_raw() == X509_V_ERR_HOSTNAME_MISMATCH => {
+                ErrorKind::HostnameMismatch
+            }
+            Error::Ssl(_, v) if v != X509VerifyResult::OK => ErrorKind::CertificateVerification(v),
+            Error::Ssl(ref e, _) => {
+                if let Some(e) = e.io_error() {
+                    ErrorKind::Io(e.kind())
+                } else {
+                    e.ssl_error()
+                        .and_then(classify_error_stack)
+                        .unwrap_or(ErrorKind::Other)
+                }
+            }
+            Error::SrtpProfile(_) => ErrorKind::SrtpNegotiation,
+            Error::HandshakeTimeout => ErrorKind::HandshakeTimeout,
+            Error::KeepaliveTimeout => ErrorKind::KeepaliveTimeout,
+            Error::Io(ref e) => ErrorKind::Io(e.kind()),
+            Error::Configuration(_) => ErrorKind::Configuration,
+        }
+    }
+
+    /// Returns the underlying OpenSSL error stack, if any.
+    pub fn error_stack(&self) -> Option<&ErrorStack> {
+        match *self {
+            Error::Normal(ref e) => Some(e),
+            Error::Ssl(ref e, _) => e.ssl_error(),
+            Error::SrtpProfile(_)
+            | Error::HandshakeTimeout
+            | Error::KeepaliveTimeout
+            | Error::Io(_)
+            | Error::Configuration(_) => None,
+        }
+    }
+}
+
+/// A classification of an `Error`, returned by `Error::kind`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The peer's certificate chain could not be verified, contains the reason of the failure.
+    CertificateVerification(X509VerifyResult),
+    /// The peer's certificate is valid, but not for the requested hostname.
+    HostnameMismatch,
+    /// The peer's stapled OCSP response was rejected by the `OcspPolicy`.
+    OcspStatus,
+    /// The peers do not support a common protocol version.
+    ProtocolVersion,
+    /// The peers do not support a common cipher, group or signature algorithm.
+    NoSharedCipher,
+    /// The SRTP profiles could not be negotiated or the selected profile is unknown.
+    SrtpNegotiation,
+    /// The handshake did not complete before its deadline.
+    HandshakeTimeout,
+    /// The peer did not answer the keepalive probes and is considered dead.
+    KeepaliveTimeout,
+    /// The peer sent an alert, contains the alert description code as defined in RFC5246.
+    ///
+    /// A `protocol_version` alert is reported as `ProtocolVersion` instead.
+    AlertReceived(u8),
+    /// The underlying stream returned an I/O error.
+    Io(io::ErrorKind),
+    /// The DTLS context could not be configured, e.g. an unknown cipher name or a malformed certificate.
+    Configuration,
+    /// Any other error, see the underlying OpenSSL error for more information.
+    Other,
+}
+
+/// `X509_V_ERR_HOSTNAME_MISMATCH`
+const X509_V_ERR_HOSTNAME_MISMATCH: i32 = 62;
+
+/// `ERR_LIB_SSL`
+const ERR_LIB_SSL: i32 = 20;
+
+/// `SSL_AD_REASON_OFFSET`, alerts received from the peer are reported as this offset plus the alert code.
+const SSL_AD_REASON_OFFSET: i32 = 1000;
+
+/// `SSL_AD_PROTOCOL_VERSION`
+const SSL_AD_PROTOCOL_VERSION: i32 = 70;
+
+/// Returns the kind of the first error in the stack with a known SSL reason code.
+///
+/// Local reasons take precedence over alerts received from the peer, which OpenSSL may report
+/// first. A received `protocol_version` alert is reported as `ErrorKind::ProtocolVersion`.
+fn classify_error_stack(stack: &ErrorStack) -> Option<ErrorKind> {
+    let ssl_errors = || {
+        stack
+            .errors()
+            .iter()
+            .filter(|e| e.library_code() == ERR_LIB_SSL)
+    };
+
+    ssl_errors()
+        .find_map(|e| {
+            let kind = match e.reason_code() {
+                // SSL_R_CERTIFICATE_VERIFY_FAILED
+                134 => ErrorKind::CertificateVerification(X509VerifyResult::OK),
+                // SSL_R_NO_SHARED_CIPHER, SSL_R_NO_CIPHERS_AVAILABLE, SSL_R_NO_SHARED_GROUPS,
+                // SSL_R_NO_SHARED_SIGNATURE_ALGORITHMS, SSL_R_NO_SUITABLE_SIGNATURE_ALGORITHM
+                193 | 181 | 410 | 376 | 118 => ErrorKind::NoSharedCipher,
+                // SSL_R_VERSION_TOO_HIGH, SSL_R_NO_PROTOCOLS_AVAILABLE, SSL_R_UNSUPPORTED_PROTOCOL,
+                // SSL_R_UNSUPPORTED_SSL_VERSION, SSL_R_WRONG_VERSION_NUMBER, SSL_R_VERSION_TOO_LOW
+                166 | 191 | 258 | 259 | 267 | 396 => ErrorKind::ProtocolVersion,
+                // SSL_R_NO_CIPHER_MATCH
+                185 => ErrorKind::Configuration,
+                // SSL_R_INVALID_STATUS_RESPONSE
+                328 => ErrorKind::OcspStatus,
+                // the SRTP reason codes differ between OpenSSL versions
+                _ if e
+                    .reason()
+                    .is_some_and(|r| r.to_ascii_lowercase().contains("srtp")) =>
+                {
+                    ErrorKind::SrtpNegotiation
+                }
+                _ => return None,
+            };
+
+            Some(kind)
+        })
+        .or_else(|| {
+            ssl_errors().find_map(|e| match e.reason_code() - SSL_AD_REASON_OFFSET {
+                SSL_AD_PROTOCOL_VERSION => Some(ErrorKind::ProtocolVersion),
+                alert @ 0..=255 => Some(ErrorKind::AlertReceived(alert as u8)),
+                _ => None,
+            })
+        })
 }
 
 impl error::Error for Error {
@@ -24,6 +166,10 @@ impl error::Error for Error {
             Error::Normal(ref e) => error::Error::description(e),
             Error::Ssl(ref e, _) => error::Error::description(e),
             Error::SrtpProfile(ref e) => error::Error::description(e),
+            Error::HandshakeTimeout => "handshake timed out",
+            Error::KeepaliveTimeout => "peer did not answer keepalive probes",
+            Error::Io(_) => "I/O error",
+            Error::Configuration(ref e) => e,
         }
     }
 
@@ -32,6 +178,10 @@ impl error::Error for Error {
             Error::Normal(ref e) => error::Error::source(e),
             Error::Ssl(ref e, _) => error::Error::source(e),
             Error::SrtpProfile(ref e) => error::Error::source(e),
+            Error::HandshakeTimeout => None,
+            Error::KeepaliveTimeout => None,
+            Error::Io(ref e) => error::Error::source(e),
+            Error::Configuration(_) => None,
         }
     }
 }
@@ -43,6 +193,10 @@ impl fmt::Display for Error {
             Error::Ssl(ref e, X509VerifyResult::OK) => fmt::Display::fmt(e, fmt),
             Error::Ssl(ref e, v) => write!(fmt, "{} ({})", e, v),
             Error::SrtpProfile(ref e) => fmt::Display::fmt(e, fmt),
+            Error::HandshakeTimeout => fmt.write_str("handshake timed out"),
+            Error::KeepaliveTimeout => fmt.write_str("peer did not answer keepalive probes"),
+            Error::Io(ref e) => fmt::Display::fmt(e, fmt),
+            Error::Configuration(ref e) => fmt.write_str(e),
         }
     }
 }
@@ -53,6 +207,12 @@ impl From<ErrorStack> for Error {
     }
 }
 
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
 impl From<SrtpProfileError> for Error {
     fn from(err: SrtpProfileError) -> Error {
         Error::SrtpProfile(err)
@@ -80,7 +240,12 @@ impl<S: fmt::Debug> From<ssl::HandshakeError<S>> for HandshakeError<S> {
             ssl::HandshakeError::SetupFailure(e) => HandshakeError::Failure(e.into()),
             ssl::HandshakeError::Failure(e) => {
                 let v = e.ssl().verify_result();
-                HandshakeError::Failure(Error::Ssl(e.into_error(), v))
+                let session = Session::get(e.ssl()).cloned();
+                let error = Error::Ssl(e.into_error(), v);
+                if let Some(session) = session {
+                    session.failed(&error);
+                }
+                HandshakeError::Failure(error)
             }
             ssl::HandshakeError::WouldBlock(s) => {
                 HandshakeError::WouldBlock(MidHandshakeDtlsStream::from(s))
@@ -1,7 +1,15 @@
 use crate::{Certificate, Error};
 use openssl::pkcs12::{ParsedPkcs12, Pkcs12};
+use std::fmt;
 
-use bytes::Bytes;
+use zeroize::Zeroizing;
+
+/// The longest pre-shared key OpenSSL accepts, `PSK_MAX_PSK_LEN` of OpenSSL 1.1.1.
+pub(crate) const MAX_PSK_LEN: usize = 256;
+
+/// The longest PSK identity or identity hint OpenSSL accepts, `PSK_MAX_IDENTITY_LEN` of OpenSSL
+/// 1.1.1.
+pub(crate) const MAX_PSK_IDENTITY_LEN: usize = 128;
 
 /// A cryptographic identity.
 ///
@@ -34,6 +42,16 @@ impl CertificateIdentity {
     }
 }
 
+/// Never prints the private key.
+impl fmt::Debug for CertificateIdentity {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("CertificateIdentity")
+            .field("subject", &self.certificate().as_ref().subject_name())
+            .field("private_key", &format_args!("<redacted>"))
+            .finish()
+    }
+}
+
 impl From<ParsedPkcs12> for CertificateIdentity {
     fn from(pkcs_12: ParsedPkcs12) -> Self {
         CertificateIdentity(pkcs_12)
@@ -46,22 +64,91 @@ impl AsRef<ParsedPkcs12> for CertificateIdentity {
     }
 }
 
-/// Identity/key for client PSK authentication.
+/// Identity/key for PSK authentication.
 ///
 /// Defaults to None
 ///
+/// Keys may be up to 256 bytes long, identities up to 128 bytes without NUL bytes; builders
+/// reject longer ones. Both are wiped from memory when dropped.
+///
 /// # Hint
 /// You should specify one of the PSK_* ciphers, i.e. PSK-AES128-CCM8
 #[derive(Clone)]
-pub struct PskIdentity(pub(crate) Bytes, pub(crate) Bytes);
+pub struct PskIdentity(pub(crate) Zeroizing<Vec<u8>>, pub(crate) Zeroizing<Vec<u8>>);
 
 impl PskIdentity {
     pub fn new(identity: &[u8], key: &[u8]) -> PskIdentity {
-        PskIdentity(Bytes::from(identity), Bytes::from(key))
+        PskIdentity(
+            Zeroizing::new(identity.to_vec()),
+            Zeroizing::new(key.to_vec()),
+        )
+    }
+
+    /// Returns the identity sent to the server.
+    pub fn identity(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Checks that OpenSSL can use the identity and key.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        if self.1.len() > MAX_PSK_LEN {
+            return Err(Error::Configuration(format!(
+                "PSK key of {} bytes exceeds the maximum of {} bytes",
+                self.1.len(),
+                MAX_PSK_LEN
+            )));
+        }
+        validate_psk_identity("PSK identity", &self.0)
+    }
+
+    /// Copies the identity and key into the buffers of OpenSSL's client callback, returning the
+    /// length of the key.
+    pub(crate) fn write_to(&self, identity: &mut [u8], psk: &mut [u8]) -> Result<usize, Error> {
+        self.validate()?;
+        if self.0.len() > identity.len() || self.1.len() > psk.len() {
+            return Err(Error::Configuration(
+                "PSK identity or key exceeds the buffers of OpenSSL".to_string(),
+            ));
+        }
+
+        identity[..self.0.len()].copy_from_slice(&self.0);
+        psk[..self.1.len()].copy_from_slice(&self.1);
+        Ok(self.1.len())
+    }
+}
+
+/// Never prints the key.
+impl fmt::Debug for PskIdentity {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("PskIdentity")
+            .field("identity", &String::from_utf8_lossy(&self.0))
+            .field("key", &format_args!("<redacted>"))
+            .finish()
+    }
+}
+
+/// Checks that OpenSSL can use `identity` as PSK identity or identity hint, which it handles as
+/// C strings.
+pub(crate) fn validate_psk_identity(what: &str, identity: &[u8]) -> Result<(), Error> {
+    if identity.len() > MAX_PSK_IDENTITY_LEN {
+        return Err(Error::Configuration(format!(
+            "{} of {} bytes exceeds the maximum of {} bytes",
+            what,
+            identity.len(),
+            MAX_PSK_IDENTITY_LEN
+        )));
+    }
+    if identity.contains(&0) {
+        return Err(Error::Configuration(format!(
+            "{} contains a NUL byte",
+            what
+        )));
     }
+    Ok(())
 }
 
 /// Possible identities for DTLS connector (client)
+#[derive(Debug)]
 pub enum ConnectorIdentity {
     Certificate(CertificateIdentity),
     Psk(PskIdentity)
@@ -0,0 +1,153 @@
+use crate::callbacks;
+use crate::Error;
+use openssl::{
+    error::ErrorStack,
+    ssl::{ExtensionContext, SslContextBuilder, SslRef},
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// The number of unanswered probes after which the peer is considered dead, by default.
+const DEFAULT_MAX_MISSED: u32 = 3;
+
+/// The extension offering keepalive, from the range of RFC8446 reserved for private use.
+const KEEPALIVE_EXTENSION: u16 = 0xff4b;
+
+/// The application data of a keepalive probe.
+pub(crate) const PING: &[u8] = b"\0udp-dtls keepalive ping\0";
+
+/// The application data answering a keepalive probe.
+pub(crate) const PONG: &[u8] = b"\0udp-dtls keepalive pong\0";
+
+/// When a session probes its peer, and how many probes it may miss.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct KeepaliveSettings {
+    pub(crate) interval: Option<Duration>,
+    pub(crate) max_missed: u32,
+}
+
+impl Default for KeepaliveSettings {
+    fn default() -> Self {
+        KeepaliveSettings {
+            interval: None,
+            max_missed: DEFAULT_MAX_MISSED,
+        }
+    }
+}
+
+/// The keepalive state of a session.
+pub(crate) struct Keepalive {
+    interval: Duration,
+    max_missed: u32,
+    /// Whether the peer enabled keepalive as well.
+    negotiated: AtomicBool,
+    state: Mutex<State>,
+}
+
+struct State {
+    /// When the next probe is due, unless a record is received before.
+    deadline: Instant,
+    /// The probes sent since the last record was received.
+    missed: u32,
+    dead: bool,
+}
+
+impl Keepalive {
+    /// Attaches keepalive probing to `ssl`, if an interval is set.
+    pub(crate) fn start(settings: KeepaliveSettings, ssl: &mut SslRef) -> Result<(), ErrorStack> {
+        let interval = match settings.interval {
+            Some(interval) => interval,
+            None => return Ok(()),
+        };
+
+        let keepalive = Keepalive {
+            interval,
+            max_missed: settings.max_missed,
+            negotiated: AtomicBool::new(false),
+            state: Mutex::new(State {
+                deadline: Instant::now() + interval,
+                missed: 0,
+                dead: false,
+            }),
+        };
+        callbacks::set_state(ssl, keepalive)?;
+        Ok(())
+    }
+
+    /// Returns the keepalive state attached to `ssl`, if any.
+    pub(crate) fn get(ssl: &SslRef) -> Option<&Keepalive> {
+        callbacks::state(ssl)
+    }
+
+    /// Returns the keepalive state attached to `ssl`, if the peer enabled keepalive as well.
+    pub(crate) fn negotiated(ssl: &SslRef) -> Option<&Keepalive> {
+        Keepalive::get(ssl).filter(|keepalive| keepalive.negotiated.load(Ordering::Acquire))
+    }
+
+    /// Returns the time left until the next probe is due.
+    pub(crate) fn timeout(&self) -> Duration {
+        let state = self.state.lock().unwrap();
+        state.deadline.saturating_duration_since(Instant::now())
+    }
+
+    /// Records that the peer is alive, called for every record received and completed handshake.
+    pub(crate) fn received(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.deadline = Instant::now() + self.interval;
+        state.missed = 0;
+    }
+
+    /// Returns whether a probe is due, counting the previous probe as missed.
+    ///
+    /// Fails with `Error::KeepaliveTimeout` once `max_missed` probes were missed.
+    pub(crate) fn expire(&self) -> Result<bool, Error> {
+        let mut state = self.state.lock().unwrap();
+        if state.dead {
+            return Err(Error::KeepaliveTimeout);
+        }
+
+        let now = Instant::now();
+        if now < state.deadline {
+            return Ok(false);
+        }
+        if state.missed >= self.max_missed {
+            state.dead = true;
+            return Err(Error::KeepaliveTimeout);
+        }
+        state.missed += 1;
+        state.deadline = now + self.interval;
+        Ok(true)
+    }
+
+    /// Returns whether the peer missed too many probes.
+    pub(crate) fn is_dead(&self) -> bool {
+        self.state.lock().unwrap().dead
+    }
+}
+
+/// Installs the extension negotiating keepalive with the peer.
+///
+/// The client offers the extension in its ClientHello and the server echoes it, as OpenSSL only
+/// answers custom extensions the client sent. Sessions only probe and consume probes once both
+/// peers saw the extension, so peers without keepalive are neither probed nor lose records.
+pub(crate) fn install_keepalive_negotiation(ctx: &mut SslContextBuilder) -> Result<(), ErrorStack> {
+    let context = ExtensionContext::DTLS_ONLY
+        | ExtensionContext::CLIENT_HELLO
+        | ExtensionContext::TLS1_2_SERVER_HELLO;
+    ctx.add_custom_ext(
+        KEEPALIVE_EXTENSION,
+        context,
+        |ssl, _, _| Ok(Keepalive::get(ssl).map(|_| &[][..])),
+        |ssl, _, _, _| {
+            if let Some(keepalive) = Keepalive::get(ssl) {
+                keepalive.negotiated.store(true, Ordering::Release);
+            }
+            Ok(())
+        },
+    )
+}
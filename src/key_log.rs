@@ -0,0 +1,86 @@
+use crate::{Error, Result};
+use log::warn;
+use openssl::ssl::SslContextBuilder;
+use std::{
+    env, fmt,
+    fs::OpenOptions,
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+/// Destination of the session secrets logged in the NSS key log format, which Wireshark can use
+/// to decrypt captured DTLS traffic.
+///
+/// Key logging is only available in debug builds, or when the `keylog` cargo feature is enabled.
+/// Building a connector or acceptor with key logging fails otherwise.
+///
+/// # Warning
+///
+/// Anyone with access to the logged secrets can decrypt the logged sessions. Never enable this in
+/// production.
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum KeyLog {
+    /// Appends the lines to the file named by the `SSLKEYLOGFILE` environment variable.
+    ///
+    /// The variable is read when the connector or acceptor is built.
+    SslKeyLogFile,
+    /// Passes each line, without line terminator, to the callback.
+    Callback(Arc<dyn Fn(&str) + Send + Sync>),
+}
+
+impl KeyLog {
+    /// Creates a `KeyLog` passing each line to the given callback.
+    pub fn callback<F>(callback: F) -> KeyLog
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        KeyLog::Callback(Arc::new(callback))
+    }
+
+    /// Installs the key log callback on the given `SslContextBuilder`.
+    pub(crate) fn install(&self, ctx: &mut SslContextBuilder) -> Result<()> {
+        if !cfg!(debug_assertions) && !cfg!(feature = "keylog") {
+            return Err(Error::Configuration(
+                "key logging requires a debug build or the `keylog` feature".to_string(),
+            ));
+        }
+
+        match self {
+            KeyLog::SslKeyLogFile => {
+                let path = env::var_os("SSLKEYLOGFILE").ok_or_else(|| {
+                    Error::Configuration("SSLKEYLOGFILE is not set".to_string())
+                })?;
+                let file = OpenOptions::new().create(true).append(true).open(&path)?;
+                let file = Mutex::new(file);
+
+                warn!(
+                    "DTLS key logging is enabled, session secrets are written to {:?}",
+                    path
+                );
+                ctx.set_keylog_callback(move |_, line| {
+                    if let Ok(mut file) = file.lock() {
+                        let _ = writeln!(file, "{}", line);
+                    }
+                });
+            }
+            KeyLog::Callback(callback) => {
+                let callback = callback.clone();
+
+                warn!("DTLS key logging is enabled, session secrets are passed to a callback");
+                ctx.set_keylog_callback(move |_, line| callback(line));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for KeyLog {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyLog::SslKeyLogFile => fmt.write_str("SslKeyLogFile"),
+            KeyLog::Callback(_) => fmt.write_str("Callback"),
+        }
+    }
+}
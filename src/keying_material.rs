@@ -0,0 +1,40 @@
+use std::{fmt, ops::Deref};
+use zeroize::Zeroizing;
+
+/// Keying material exported from a DTLS session, e.g. the SRTP master keys and salts.
+///
+/// The material is wiped from memory when dropped, and never printed by `Debug`.
+#[derive(Clone)]
+pub struct KeyingMaterial(Zeroizing<Vec<u8>>);
+
+impl KeyingMaterial {
+    /// Creates zeroed keying material of `len` bytes, to be filled by OpenSSL.
+    pub(crate) fn zeroed(len: usize) -> KeyingMaterial {
+        KeyingMaterial(Zeroizing::new(vec![0; len]))
+    }
+
+    pub(crate) fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+impl Deref for KeyingMaterial {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for KeyingMaterial {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Never prints the material itself.
+impl fmt::Debug for KeyingMaterial {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "KeyingMaterial(<{} bytes redacted>)", self.0.len())
+    }
+}
@@ -1,30 +1,78 @@
 //! An rusty abstraction over OpenSSL DTLS.
 
+mod alert;
+mod callbacks;
 mod certificate;
 mod certificate_fingerprint;
+mod cipher_preset;
+#[cfg(feature = "serde")]
+mod config;
+mod crl_check;
+mod datagram_queue;
 mod dtls_acceptor;
 mod dtls_acceptor_builder;
 mod dtls_connection_builder;
 mod dtls_connector;
+mod dtls_session;
 mod dtls_stream;
 mod error;
 mod identity;
+mod keepalive;
+mod key_log;
+mod keying_material;
+mod metrics;
 mod midhandshake_dtls_steam;
+#[cfg(feature = "mio")]
+mod mio_udp_channel;
+mod ocsp;
 mod openssl;
 mod protocol;
+mod public_key;
+mod read_timeout;
+mod records;
+mod rekey;
+mod session_table;
+mod session_table_builder;
+mod shutdown_result;
+mod split;
 mod srtp_profile;
+mod trace;
+mod try_clone_socket;
+mod udp_batch;
 mod udp_channel;
 
+pub use self::alert::{Alert, AlertDescription, AlertLevel};
 pub use self::certificate::Certificate;
 pub use self::certificate_fingerprint::{CertificateFingerprint, SignatureAlgorithm};
+pub use self::cipher_preset::CipherPreset;
+#[cfg(feature = "serde")]
+pub use self::config::{AcceptorConfig, ConnectorConfig, IdentityConfig, PasswordSource};
+pub use self::crl_check::CrlCheck;
+pub use self::datagram_queue::DatagramQueue;
 pub use self::dtls_acceptor::DtlsAcceptor;
 pub use self::dtls_acceptor_builder::DtlsAcceptorBuilder;
 pub use self::dtls_connection_builder::DtlsConnectorBuilder;
 pub use self::dtls_connector::DtlsConnector;
-pub use self::dtls_stream::DtlsStream;
-pub use self::error::{Error, HandshakeError, Result, SrtpProfileError};
+pub use self::dtls_session::DtlsSession;
+pub use self::dtls_stream::{DtlsStream, MAX_DATAGRAM_SIZE};
+pub use self::error::{Error, ErrorKind, HandshakeError, Result, SrtpProfileError};
 pub use self::identity::{ConnectorIdentity, CertificateIdentity, PskIdentity};
+pub use self::key_log::KeyLog;
+pub use self::keying_material::KeyingMaterial;
+pub use self::metrics::{InMemoryMetrics, MetricsObserver, Role, SessionId};
 pub use self::midhandshake_dtls_steam::MidHandshakeDtlsStream;
+#[cfg(feature = "mio")]
+pub use self::mio_udp_channel::MioUdpChannel;
+pub use self::ocsp::{OcspPolicy, OcspStaple, OcspStatus};
 pub use self::protocol::Protocol;
+pub use self::public_key::PublicKey;
+pub use self::read_timeout::ReadTimeout;
+pub use self::records::{InvalidRecordPolicy, RecordStats};
+pub use self::session_table::{EvictionReason, SessionTable};
+pub use self::session_table_builder::SessionTableBuilder;
+pub use self::shutdown_result::ShutdownResult;
+pub use self::split::{ReadHalf, WriteHalf};
 pub use self::srtp_profile::SrtpProfile;
+pub use self::try_clone_socket::TryCloneSocket;
+pub use self::udp_batch::UdpBatch;
 pub use self::udp_channel::UdpChannel;
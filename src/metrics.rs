@@ -0,0 +1,227 @@
+use crate::callbacks;
+use crate::{Error, ErrorKind};
+use openssl::{error::ErrorStack, ssl::SslRef};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// The side of a DTLS session.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// The session was initiated by `DtlsConnector`.
+    Client,
+    /// The session was accepted by `DtlsAcceptor`.
+    Server,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Role::Client => fmt.write_str("client"),
+            Role::Server => fmt.write_str("server"),
+        }
+    }
+}
+
+/// Identifies a session in the callbacks of a `MetricsObserver`.
+///
+/// Identifiers are unique within the process.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SessionId(u64);
+
+impl fmt::Display for SessionId {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, fmt)
+    }
+}
+
+/// Receives events of the sessions created by a connector or acceptor.
+///
+/// All methods default to doing nothing. They are called on the thread driving the session, so
+/// implementations should return quickly.
+pub trait MetricsObserver: Send + Sync {
+    /// A handshake was started.
+    fn handshake_started(&self, _session: SessionId, _role: Role) {}
+
+    /// A handshake completed successfully, `duration` is measured from its start.
+    fn handshake_completed(&self, _session: SessionId, _role: Role, _duration: Duration) {}
+
+    /// A handshake failed.
+    fn handshake_failed(&self, _session: SessionId, _role: Role, _kind: ErrorKind) {}
+
+    /// The last flight of handshake messages was retransmitted because the peer did not answer in
+    /// time.
+    fn retransmission(&self, _session: SessionId, _role: Role) {}
+
+    /// Application data was received.
+    fn bytes_received(&self, _session: SessionId, _bytes: usize) {}
+
+    /// Application data was sent.
+    fn bytes_sent(&self, _session: SessionId, _bytes: usize) {}
+}
+
+/// A `MetricsObserver` counting all events in memory, useful for tests.
+#[derive(Debug, Default)]
+pub struct InMemoryMetrics {
+    handshakes_started: AtomicU64,
+    handshakes_completed: AtomicU64,
+    retransmissions: AtomicU64,
+    bytes_received: AtomicU64,
+    bytes_sent: AtomicU64,
+    handshake_durations: Mutex<Vec<Duration>>,
+    failures: Mutex<Vec<ErrorKind>>,
+    sessions: Mutex<HashMap<SessionId, (u64, u64)>>,
+}
+
+impl InMemoryMetrics {
+    /// Creates an `InMemoryMetrics` with all counters at zero.
+    pub fn new() -> InMemoryMetrics {
+        InMemoryMetrics::default()
+    }
+
+    /// Returns the number of handshakes started.
+    pub fn handshakes_started(&self) -> u64 {
+        self.handshakes_started.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of handshakes completed successfully.
+    pub fn handshakes_completed(&self) -> u64 {
+        self.handshakes_completed.load(Ordering::Relaxed)
+    }
+
+    /// Returns the durations of the completed handshakes, in order of completion.
+    pub fn handshake_durations(&self) -> Vec<Duration> {
+        self.handshake_durations.lock().unwrap().clone()
+    }
+
+    /// Returns the reasons of the failed handshakes, in order of failure.
+    pub fn failures(&self) -> Vec<ErrorKind> {
+        self.failures.lock().unwrap().clone()
+    }
+
+    /// Returns the number of retransmitted flights.
+    pub fn retransmissions(&self) -> u64 {
+        self.retransmissions.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of application bytes received over all sessions.
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of application bytes sent over all sessions.
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of application bytes received and sent by the given session.
+    pub fn session_bytes(&self, session: SessionId) -> (u64, u64) {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(&session)
+            .cloned()
+            .unwrap_or((0, 0))
+    }
+}
+
+impl MetricsObserver for InMemoryMetrics {
+    fn handshake_started(&self, _session: SessionId, _role: Role) {
+        self.handshakes_started.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn handshake_completed(&self, _session: SessionId, _role: Role, duration: Duration) {
+        self.handshakes_completed.fetch_add(1, Ordering::Relaxed);
+        self.handshake_durations.lock().unwrap().push(duration);
+    }
+
+    fn handshake_failed(&self, _session: SessionId, _role: Role, kind: ErrorKind) {
+        self.failures.lock().unwrap().push(kind);
+    }
+
+    fn retransmission(&self, _session: SessionId, _role: Role) {
+        self.retransmissions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn bytes_received(&self, session: SessionId, bytes: usize) {
+        self.bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+        self.sessions.lock().unwrap().entry(session).or_default().0 += bytes as u64;
+    }
+
+    fn bytes_sent(&self, session: SessionId, bytes: usize) {
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.sessions.lock().unwrap().entry(session).or_default().1 += bytes as u64;
+    }
+}
+
+/// Per session state of an observed session.
+#[derive(Clone)]
+pub(crate) struct Session {
+    id: SessionId,
+    role: Role,
+    started: Instant,
+    observer: Arc<dyn MetricsObserver>,
+}
+
+impl Session {
+    /// Attaches a new observed session to `ssl` and reports the start of its handshake.
+    pub(crate) fn start(
+        observer: &Arc<dyn MetricsObserver>,
+        role: Role,
+        ssl: &mut SslRef,
+    ) -> Result<(), ErrorStack> {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+        let id = SessionId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        let session = Session {
+            id,
+            role,
+            started: Instant::now(),
+            observer: observer.clone(),
+        };
+
+        callbacks::set_state(ssl, session)?;
+        callbacks::install_timer_callback(ssl);
+
+        observer.handshake_started(id, role);
+        Ok(())
+    }
+
+    /// Returns the observed session attached to `ssl`, if any.
+    pub(crate) fn get(ssl: &SslRef) -> Option<&Session> {
+        callbacks::state(ssl)
+    }
+
+    pub(crate) fn id(&self) -> SessionId {
+        self.id
+    }
+
+    pub(crate) fn completed(&self) {
+        self.observer
+            .handshake_completed(self.id, self.role, self.started.elapsed());
+    }
+
+    pub(crate) fn failed(&self, error: &Error) {
+        self.observer
+            .handshake_failed(self.id, self.role, error.kind());
+    }
+
+    pub(crate) fn received(&self, bytes: usize) {
+        self.observer.bytes_received(self.id, bytes);
+    }
+
+    pub(crate) fn sent(&self, bytes: usize) {
+        self.observer.bytes_sent(self.id, bytes);
+    }
+
+    pub(crate) fn retransmission(&self) {
+        self.observer.retransmission(self.id, self.role);
+    }
+}
@@ -1,19 +1,60 @@
-use crate::{DtlsStream, HandshakeError};
-use openssl::ssl::MidHandshakeSslStream;
-use std::{fmt, io};
+use crate::metrics::Session;
+use crate::openssl::{dtls_get_timeout, dtls_handle_timeout};
+use crate::{DtlsStream, Error, HandshakeError, ReadTimeout};
+use openssl::ssl::{self, MidHandshakeSslStream, SslRef};
+use std::{
+    cmp, fmt, io,
+    time::{Duration, Instant},
+};
+
+/// The longest a blocking stream waits for the peer before OpenSSL gets a chance to retransmit.
+const RETRANSMIT_INTERVAL: Duration = Duration::from_secs(1);
 
 /// A DTLS stream which has been interrupted midway through the handshake process.
-pub struct MidHandshakeDtlsStream<S>(MidHandshakeSslStream<S>);
+pub struct MidHandshakeDtlsStream<S> {
+    stream: MidHandshakeSslStream<S>,
+    deadline: Option<Instant>,
+}
 
 impl<S> MidHandshakeDtlsStream<S> {
     /// Returns a shared reference to the inner stream.
     pub fn get_ref(&self) -> &S {
-        self.0.get_ref()
+        self.stream.get_ref()
     }
 
     /// Returns a mutable reference to the inner stream.
     pub fn get_mut(&mut self) -> &mut S {
-        self.0.get_mut()
+        self.stream.get_mut()
+    }
+
+    /// Returns the instant at which the handshake fails with `Error::HandshakeTimeout`, if any.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Returns the time left until the retransmission timer expires, if it is running.
+    ///
+    /// Event loops should wake up after this duration and call `handle_timeout`, or resume the
+    /// handshake, which handles an expired timer as well.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`DTLSv1_get_timeout`].
+    ///
+    /// [`DTLSv1_get_timeout`]: https://www.openssl.org/docs/manmaster/man3/DTLSv1_get_timeout.html
+    pub fn timeout(&self) -> Option<Duration> {
+        dtls_get_timeout(self.stream.ssl())
+    }
+
+    /// Retransmits the last flight of handshake messages if the retransmission timer expired.
+    ///
+    /// Returns whether the timer had expired.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`DTLSv1_handle_timeout`].
+    ///
+    /// [`DTLSv1_handle_timeout`]: https://www.openssl.org/docs/manmaster/man3/DTLSv1_handle_timeout.html
+    pub fn handle_timeout(&mut self) -> Result<bool, Error> {
+        Ok(dtls_handle_timeout(self.stream.ssl())?)
     }
 }
 
@@ -29,6 +70,8 @@ where
     /// stream is an asynchronous one then `HandshakeError::WouldBlock` may
     /// just mean to wait for more I/O to happen later.
     ///
+    /// If the deadline of the handshake has passed, `Error::HandshakeTimeout` is returned and the
+    /// underlying stream is dropped.
     ///
     /// # Underlying SSL
     ///
@@ -36,30 +79,127 @@ where
     ///
     /// [`SSL_do_handshake`]: https://www.openssl.org/docs/manmaster/man3/SSL_do_handshake.html
     pub fn handshake(self) -> Result<DtlsStream<S>, HandshakeError<S>> {
-        match self.0.handshake() {
-            Ok(s) => Ok(DtlsStream::from(s)),
-            Err(e) => Err(e.into()),
+        if let Some(deadline) = self.deadline {
+            if Instant::now() >= deadline {
+                return Err(timed_out(self.stream.ssl()));
+            }
+        }
+
+        let deadline = self.deadline;
+        match self.stream.handshake() {
+            Ok(s) => Ok(DtlsStream::established(s)),
+            Err(e) => Err(HandshakeError::from(e).with_deadline(deadline)),
         }
     }
 }
 
+/// Runs the handshake started by `start` until it completes or `deadline` passes.
+///
+/// Streams that support read timeouts are driven to completion here, their read timeout is
+/// restored afterwards. Other streams get the deadline attached to the returned
+/// `MidHandshakeDtlsStream`.
+pub(crate) fn handshake_until<S, F>(
+    mut stream: S,
+    deadline: Instant,
+    start: F,
+) -> Result<DtlsStream<S>, HandshakeError<S>>
+where
+    S: io::Read + io::Write + ReadTimeout + fmt::Debug,
+    F: FnOnce(S) -> Result<ssl::SslStream<S>, ssl::HandshakeError<S>>,
+{
+    let previous = match stream.read_timeout() {
+        Ok(previous) => previous,
+        Err(ref e) if e.kind() == io::ErrorKind::Unsupported => {
+            return match start(stream) {
+                Ok(s) => Ok(DtlsStream::established(s)),
+                Err(e) => Err(HandshakeError::from(e).with_deadline(Some(deadline))),
+            };
+        }
+        Err(e) => return Err(HandshakeError::Failure(e.into())),
+    };
+
+    set_step_timeout(&mut stream, deadline)?;
+
+    let mut result = start(stream);
+    loop {
+        match result {
+            Ok(mut s) => {
+                s.get_mut()
+                    .set_read_timeout(previous)
+                    .map_err(|e| HandshakeError::Failure(e.into()))?;
+                return Ok(DtlsStream::established(s));
+            }
+            Err(ssl::HandshakeError::WouldBlock(mut s)) => {
+                if let Err(e) = set_step_timeout(s.get_mut(), deadline) {
+                    if let HandshakeError::Failure(Error::HandshakeTimeout) = e {
+                        return Err(timed_out(s.ssl()));
+                    }
+                    return Err(e);
+                }
+                result = s.handshake();
+            }
+            Err(e) => return Err(HandshakeError::from(e)),
+        }
+    }
+}
+
+/// Bounds the next read of the stream by the deadline and the retransmission interval.
+fn set_step_timeout<S: ReadTimeout + fmt::Debug>(
+    stream: &mut S,
+    deadline: Instant,
+) -> Result<(), HandshakeError<S>> {
+    let now = Instant::now();
+    if now >= deadline {
+        return Err(HandshakeError::Failure(Error::HandshakeTimeout));
+    }
+
+    stream
+        .set_read_timeout(Some(cmp::min(deadline - now, RETRANSMIT_INTERVAL)))
+        .map_err(|e| HandshakeError::Failure(e.into()))
+}
+
+/// Reports the timed out handshake of `ssl` to its metrics observer, if any.
+fn timed_out<S: fmt::Debug>(ssl: &SslRef) -> HandshakeError<S> {
+    let error = Error::HandshakeTimeout;
+    if let Some(session) = Session::get(ssl) {
+        session.failed(&error);
+    }
+    HandshakeError::Failure(error)
+}
+
 impl<S> fmt::Debug for MidHandshakeDtlsStream<S>
 where
     S: fmt::Debug,
 {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(&self.0, fmt)
+        fmt::Debug::fmt(&self.stream, fmt)
     }
 }
 
 impl<S: fmt::Debug> AsRef<MidHandshakeSslStream<S>> for MidHandshakeDtlsStream<S> {
     fn as_ref(&self) -> &MidHandshakeSslStream<S> {
-        &self.0
+        &self.stream
     }
 }
 
 impl<S: fmt::Debug> From<MidHandshakeSslStream<S>> for MidHandshakeDtlsStream<S> {
     fn from(stream: MidHandshakeSslStream<S>) -> Self {
-        MidHandshakeDtlsStream(stream)
+        MidHandshakeDtlsStream {
+            stream,
+            deadline: None,
+        }
+    }
+}
+
+impl<S: fmt::Debug> HandshakeError<S> {
+    /// Attaches the deadline to the interrupted handshake, if the handshake was interrupted.
+    pub(crate) fn with_deadline(self, deadline: Option<Instant>) -> HandshakeError<S> {
+        match self {
+            HandshakeError::WouldBlock(mut s) => {
+                s.deadline = deadline;
+                HandshakeError::WouldBlock(s)
+            }
+            e => e,
+        }
     }
 }
@@ -0,0 +1,83 @@
+use crate::{DtlsStream, MidHandshakeDtlsStream, ReadTimeout, TryCloneSocket};
+use mio::{event::Source, net::UdpSocket, Interest, Registry, Token};
+use std::io::{Read, Result, Write};
+use std::net::SocketAddr;
+
+/// Wrapper to read from and sent data to an remote UDP endpoint with a nonblocking `mio` socket.
+///
+/// Reads and writes return `io::ErrorKind::WouldBlock` when the socket is not ready, which
+/// surfaces as `HandshakeError::WouldBlock` during the handshake and as `WouldBlock` errors on
+/// the `DtlsStream` afterwards. `mio` delivers edge-triggered events, so keep reading until
+/// `WouldBlock` is returned after each readable event.
+///
+/// `DtlsStream` and `MidHandshakeDtlsStream` over this channel can be registered with a
+/// `mio::Poll` directly.
+#[derive(Debug)]
+pub struct MioUdpChannel {
+    pub socket: UdpSocket,
+    pub remote_addr: SocketAddr,
+}
+
+impl Read for MioUdpChannel {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.socket.recv(buf)
+    }
+}
+
+impl Write for MioUdpChannel {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.socket.send_to(buf, self.remote_addr)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Nonblocking sockets have no read timeout, handshake deadlines are checked when the handshake
+/// is resumed.
+impl ReadTimeout for MioUdpChannel {}
+
+impl TryCloneSocket for MioUdpChannel {}
+
+impl Source for MioUdpChannel {
+    fn register(&mut self, registry: &Registry, token: Token, interests: Interest) -> Result<()> {
+        self.socket.register(registry, token, interests)
+    }
+
+    fn reregister(&mut self, registry: &Registry, token: Token, interests: Interest) -> Result<()> {
+        self.socket.reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &Registry) -> Result<()> {
+        self.socket.deregister(registry)
+    }
+}
+
+impl<S: Source + Read + Write> Source for DtlsStream<S> {
+    fn register(&mut self, registry: &Registry, token: Token, interests: Interest) -> Result<()> {
+        self.get_mut().register(registry, token, interests)
+    }
+
+    fn reregister(&mut self, registry: &Registry, token: Token, interests: Interest) -> Result<()> {
+        self.get_mut().reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &Registry) -> Result<()> {
+        self.get_mut().deregister(registry)
+    }
+}
+
+impl<S: Source> Source for MidHandshakeDtlsStream<S> {
+    fn register(&mut self, registry: &Registry, token: Token, interests: Interest) -> Result<()> {
+        self.get_mut().register(registry, token, interests)
+    }
+
+    fn reregister(&mut self, registry: &Registry, token: Token, interests: Interest) -> Result<()> {
+        self.get_mut().reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &Registry) -> Result<()> {
+        self.get_mut().deregister(registry)
+    }
+}
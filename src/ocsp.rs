@@ -0,0 +1,225 @@
+use crate::callbacks;
+use crate::{Error, Result};
+use openssl::{
+    hash::MessageDigest,
+    ocsp::{OcspCertId, OcspCertIdRef, OcspCertStatus, OcspFlag, OcspResponse, OcspResponseStatus},
+    ssl::{SslContextBuilder, SslRef},
+    stack::StackRef,
+    x509::{X509Ref, X509VerifyResult, X509},
+};
+use std::{
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+/// The tolerated clock skew when checking the validity period of a stapled response, in seconds.
+const VALIDITY_LEEWAY: u32 = 300;
+
+/// How a `DtlsConnector` reacts to the OCSP response stapled by the server.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum OcspPolicy {
+    /// The handshake only fails if a valid response reports the server's certificate as revoked.
+    ///
+    /// A missing, invalid or inconclusive response is tolerated and reported by
+    /// `DtlsStream::ocsp_status`.
+    Soft,
+    /// The handshake fails unless a valid response reports the server's certificate as good.
+    Hard,
+}
+
+/// The revocation status of the peer's certificate according to its stapled OCSP response, see
+/// `DtlsStream::ocsp_status`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum OcspStatus {
+    /// The certificate is not revoked.
+    Good,
+    /// The certificate is revoked.
+    Revoked,
+    /// The responder does not know the certificate.
+    Unknown,
+    /// The server did not staple a response.
+    NotStapled,
+    /// The stapled response could not be parsed or verified, does not cover the certificate, or
+    /// is outside of its validity period.
+    Invalid,
+}
+
+impl OcspStatus {
+    /// Returns the status determined during the handshake of `ssl`, if stapling was requested.
+    pub(crate) fn get(ssl: &SslRef) -> Option<OcspStatus> {
+        callbacks::state(ssl).copied()
+    }
+}
+
+impl fmt::Display for OcspStatus {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let status = match self {
+            OcspStatus::Good => "good",
+            OcspStatus::Revoked => "revoked",
+            OcspStatus::Unknown => "unknown",
+            OcspStatus::NotStapled => "not stapled",
+            OcspStatus::Invalid => "invalid",
+        };
+        fmt.write_str(status)
+    }
+}
+
+/// A DER-encoded OCSP response stapled to the handshakes of a `DtlsAcceptor`.
+///
+/// Clones share the response, so the application keeps a clone to replace the response before it
+/// expires. Handshakes started afterwards staple the new response.
+#[derive(Clone, Default)]
+pub struct OcspStaple(Arc<RwLock<Option<Vec<u8>>>>);
+
+impl OcspStaple {
+    /// Creates a staple without a response, nothing is stapled until `set` is called.
+    pub fn new() -> OcspStaple {
+        OcspStaple::default()
+    }
+
+    /// Creates a staple with the given DER-encoded response.
+    pub fn from_der(der: &[u8]) -> Result<OcspStaple> {
+        let staple = OcspStaple::new();
+        staple.set(der)?;
+        Ok(staple)
+    }
+
+    /// Replaces the stapled response with the given DER-encoded response.
+    ///
+    /// Fails with `Error::Configuration` if the response can not be parsed.
+    pub fn set(&self, der: &[u8]) -> Result<()> {
+        OcspResponse::from_der(der)
+            .map_err(|e| Error::Configuration(format!("invalid OCSP response: {}", e)))?;
+        if let Ok(mut response) = self.0.write() {
+            *response = Some(der.to_vec());
+        }
+        Ok(())
+    }
+
+    /// Removes the stapled response.
+    pub fn clear(&self) {
+        if let Ok(mut response) = self.0.write() {
+            *response = None;
+        }
+    }
+
+    /// Returns whether a response is stapled.
+    pub fn is_set(&self) -> bool {
+        self.0.read().is_ok_and(|response| response.is_some())
+    }
+
+    /// Installs the status callback stapling the current response for `certificate` on the
+    /// server side.
+    ///
+    /// OpenSSL 3.6 and newer silently skip the CertificateStatus message of a response not
+    /// covering the certificate, or of a self-signed certificate, after the server announced it,
+    /// so the client waits for it until the handshake times out. Such responses are not stapled,
+    /// and without the issuer of `certificate` in `chain` the coverage can not be checked.
+    pub(crate) fn install(
+        &self,
+        certificate: &X509Ref,
+        chain: Option<&StackRef<X509>>,
+        ctx: &mut SslContextBuilder,
+    ) -> Result<()> {
+        let id = chain
+            .into_iter()
+            .flat_map(|chain| chain.iter())
+            .find(|candidate| candidate.issued(certificate) == X509VerifyResult::OK)
+            .map(|issuer| OcspCertId::from_cert(MessageDigest::sha1(), certificate, issuer))
+            .transpose()?;
+
+        let staple = self.clone();
+        ctx.set_status_callback(move |ssl| {
+            let response = match staple.0.read() {
+                Ok(response) => response,
+                Err(_) => return Ok(false),
+            };
+            match *response {
+                Some(ref der) if id.as_ref().is_some_and(|id| covers(id, der)) => {
+                    ssl.set_ocsp_status(der).map(|_| true)
+                }
+                _ => Ok(false),
+            }
+        })?;
+        Ok(())
+    }
+}
+
+/// Returns whether the response `der` holds the status of the certificate identified by `id`.
+fn covers(id: &OcspCertIdRef, der: &[u8]) -> bool {
+    OcspResponse::from_der(der)
+        .and_then(|response| response.basic())
+        .is_ok_and(|basic| basic.find_status(id).is_some())
+}
+
+impl fmt::Debug for OcspStaple {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("OcspStaple")
+            .field("is_set", &self.is_set())
+            .finish()
+    }
+}
+
+/// Installs the status callback validating the stapled response on the client side.
+///
+/// The status is stored with each session. Stapling still has to be requested per session with
+/// `SslRef::set_status_type`.
+pub(crate) fn install_ocsp_validation(
+    policy: OcspPolicy,
+    ctx: &mut SslContextBuilder,
+) -> Result<()> {
+    ctx.set_status_callback(move |ssl| {
+        let status = validate(ssl);
+        callbacks::set_state(ssl, status)?;
+
+        Ok(match policy {
+            OcspPolicy::Soft => status != OcspStatus::Revoked,
+            OcspPolicy::Hard => status == OcspStatus::Good,
+        })
+    })?;
+    Ok(())
+}
+
+fn validate(ssl: &SslRef) -> OcspStatus {
+    let der = match ssl.ocsp_status() {
+        Some(der) => der,
+        None => return OcspStatus::NotStapled,
+    };
+
+    let status = (|| {
+        let response = OcspResponse::from_der(der).ok()?;
+        if response.status() != OcspResponseStatus::SUCCESSFUL {
+            return None;
+        }
+        let basic = response.basic().ok()?;
+
+        let chain = ssl.peer_cert_chain()?;
+        let certificate = ssl.peer_certificate()?;
+        let issuer = issuer(ssl, &certificate)?;
+        basic
+            .verify(chain, ssl.ssl_context().cert_store(), OcspFlag::empty())
+            .ok()?;
+
+        let id = OcspCertId::from_cert(MessageDigest::sha1(), &certificate, issuer).ok()?;
+        let status = basic.find_status(&id)?;
+        status.check_validity(VALIDITY_LEEWAY, None).ok()?;
+        Some(status.status)
+    })();
+
+    match status {
+        Some(OcspCertStatus::GOOD) => OcspStatus::Good,
+        Some(OcspCertStatus::REVOKED) => OcspStatus::Revoked,
+        Some(_) => OcspStatus::Unknown,
+        None => OcspStatus::Invalid,
+    }
+}
+
+/// Finds the issuer of `certificate` in the verified chain or the chain sent by the peer.
+fn issuer<'a>(ssl: &'a SslRef, certificate: &X509Ref) -> Option<&'a X509Ref> {
+    ssl.verified_chain()
+        .into_iter()
+        .chain(ssl.peer_cert_chain())
+        .flat_map(|chain| chain.iter())
+        .find(|candidate| candidate.issued(certificate) == X509VerifyResult::OK)
+}
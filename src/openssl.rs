@@ -1,10 +1,24 @@
+use foreign_types::ForeignTypeRef;
+use libc::{c_char, c_int, c_long, c_uint, c_void, size_t};
 use openssl::{
     error::ErrorStack,
-    ssl::{SslContextBuilder, SslOptions},
+    ssl::{SslContextBuilder, SslFiletype, SslOptions, SslRef, SslVerifyMode},
+    x509::{
+        store::{X509Lookup, X509StoreBuilder},
+        verify::X509VerifyFlags,
+        X509VerifyResult,
+    },
+};
+use std::{
+    ffi::{CStr, CString},
+    path::Path,
+    path::PathBuf,
+    ptr,
+    sync::Once,
+    time::Duration,
 };
-use std::sync::Once;
 
-use crate::Protocol;
+use crate::{CrlCheck, Error, Protocol, PublicKey};
 
 /// Sets protocol version requirements for the given `SslContextBuilder`
 ///
@@ -35,7 +49,301 @@ pub fn try_set_supported_protocols(
     Ok(())
 }
 
+/// Sets the cipher, group and signature algorithm lists for the given `SslContextBuilder`
+///
+/// Empty lists leave the current settings of the context untouched.
+pub fn try_set_cipher_lists(
+    ciphers: &[String],
+    groups: &[String],
+    signature_algorithms: &[String],
+    ctx: &mut SslContextBuilder,
+) -> Result<(), ErrorStack> {
+    if !ciphers.is_empty() {
+        ctx.set_cipher_list(&ciphers.join(":"))?;
+    }
+
+    if !groups.is_empty() {
+        ctx.set_groups_list(&groups.join(":"))?;
+    }
+
+    if !signature_algorithms.is_empty() {
+        ctx.set_sigalgs_list(&signature_algorithms.join(":"))?;
+    }
+
+    Ok(())
+}
+
+/// Sets up the certificate store of the given `SslContextBuilder`
+///
+/// - Replaces the store with an empty one unless the system's roots are used
+/// - Loads the CA bundle files, directories and CRL files
+/// - Enables CRL checking
+pub fn try_set_trust(
+    use_system_roots: bool,
+    root_files: &[PathBuf],
+    root_dirs: &[PathBuf],
+    crl_files: &[PathBuf],
+    crl_check: Option<CrlCheck>,
+    ctx: &mut SslContextBuilder,
+) -> Result<(), Error> {
+    if !use_system_roots {
+        ctx.set_cert_store(X509StoreBuilder::new()?.build());
+    }
+
+    let store = ctx.cert_store_mut();
+    for path in root_files {
+        store
+            .add_lookup(X509Lookup::file())?
+            .load_cert_file(utf8_path(path)?, SslFiletype::PEM)
+            .map_err(|e| path_error(path, e))?;
+    }
+    for path in root_dirs {
+        store
+            .add_lookup(X509Lookup::hash_dir())?
+            .add_dir(utf8_path(path)?, SslFiletype::PEM)
+            .map_err(|e| path_error(path, e))?;
+    }
+    for path in crl_files {
+        store
+            .add_lookup(X509Lookup::file())?
+            .load_crl_file(utf8_path(path)?, SslFiletype::PEM)
+            .map_err(|e| path_error(path, e))?;
+    }
+
+    match crl_check {
+        None => {}
+        Some(CrlCheck::Leaf) => store.set_flags(X509VerifyFlags::CRL_CHECK)?,
+        Some(CrlCheck::Chain) => {
+            store.set_flags(X509VerifyFlags::CRL_CHECK | X509VerifyFlags::CRL_CHECK_ALL)?
+        }
+    }
+
+    Ok(())
+}
+
+/// Authenticates the peer by the public key of its certificate for the given `SslContextBuilder`
+///
+/// - Requires the peer to send a certificate
+/// - Accepts the peer's certificate if its public key is pinned, whether or not its chain,
+///   validity period and hostname verify
+/// - Rejects any other certificate with `X509_V_ERR_APPLICATION_VERIFICATION`
+pub fn try_set_certificate_key_pins(
+    pins: &[PublicKey],
+    ctx: &mut SslContextBuilder,
+) -> Result<(), Error> {
+    let pins = pins
+        .iter()
+        .map(PublicKey::to_der)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mode = SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT;
+    ctx.set_verify_callback(mode, move |_, store| {
+        // errors of the issuers are settled by the verdict on the peer's own certificate
+        if store.error_depth() != 0 {
+            return true;
+        }
+
+        let pinned = store
+            .current_cert()
+            .and_then(|cert| cert.public_key().ok())
+            .and_then(|key| key.public_key_to_der().ok())
+            .is_some_and(|der| pins.contains(&der));
+        if pinned {
+            store.set_error(X509VerifyResult::OK);
+        } else {
+            store.set_error(X509VerifyResult::APPLICATION_VERIFICATION);
+        }
+        pinned
+    });
+
+    Ok(())
+}
+
+/// `rust-openssl` panics on paths which are not valid UTF-8.
+fn utf8_path(path: &Path) -> Result<&str, Error> {
+    path.to_str()
+        .ok_or_else(|| Error::Configuration(format!("{}: not valid UTF-8", path.display())))
+}
+
+fn path_error(path: &Path, error: ErrorStack) -> Error {
+    Error::Configuration(format!("{}: {}", path.display(), error))
+}
+
+const DTLS_CTRL_GET_TIMEOUT: c_int = 73;
+const DTLS_CTRL_HANDLE_TIMEOUT: c_int = 74;
+
+/// Returns the time left until the DTLS retransmission timer expires, if the timer is running.
+///
+/// This corresponds to `DTLSv1_get_timeout`, which `rust-openssl` does not expose.
+pub fn dtls_get_timeout(ssl: &SslRef) -> Option<Duration> {
+    let mut timeout = libc::timeval {
+        tv_sec: 0,
+        tv_usec: 0,
+    };
+
+    let running = unsafe {
+        openssl_sys::SSL_ctrl(
+            ssl.as_ptr(),
+            DTLS_CTRL_GET_TIMEOUT,
+            0,
+            &mut timeout as *mut libc::timeval as *mut c_void,
+        )
+    };
+
+    if running == 1 {
+        Some(Duration::new(
+            timeout.tv_sec as u64,
+            timeout.tv_usec as u32 * 1000,
+        ))
+    } else {
+        None
+    }
+}
+
+/// Retransmits the last flight of handshake messages if the DTLS retransmission timer expired.
+///
+/// Returns whether the timer had expired. This corresponds to `DTLSv1_handle_timeout`, which
+/// `rust-openssl` does not expose.
+pub fn dtls_handle_timeout(ssl: &SslRef) -> Result<bool, ErrorStack> {
+    let result: c_long = unsafe {
+        openssl_sys::SSL_ctrl(
+            ssl.as_ptr(),
+            DTLS_CTRL_HANDLE_TIMEOUT,
+            0,
+            ptr::null_mut(),
+        )
+    };
+
+    match result {
+        0 => Ok(false),
+        r if r > 0 => Ok(true),
+        _ => Err(ErrorStack::get()),
+    }
+}
+
+/// Callback receiving handshake state changes and alerts, see `SSL_set_info_callback`.
+pub type InfoCallback = unsafe extern "C" fn(*const openssl_sys::SSL, c_int, c_int);
+
+/// Callback receiving every protocol message, see `SSL_set_msg_callback`.
+pub type MsgCallback = unsafe extern "C" fn(
+    c_int,
+    c_int,
+    c_int,
+    *const c_void,
+    size_t,
+    *mut openssl_sys::SSL,
+    *mut c_void,
+);
+
+extern "C" {
+    fn DTLS_set_timer_cb(
+        ssl: *mut openssl_sys::SSL,
+        cb: Option<unsafe extern "C" fn(*mut openssl_sys::SSL, c_uint) -> c_uint>,
+    );
+    fn SSL_set_info_callback(ssl: *mut openssl_sys::SSL, cb: Option<InfoCallback>);
+    fn SSL_set_msg_callback(ssl: *mut openssl_sys::SSL, cb: Option<MsgCallback>);
+    fn SSL_alert_type_string_long(value: c_int) -> *const c_char;
+    fn SSL_alert_desc_string_long(value: c_int) -> *const c_char;
+    fn SSL_renegotiate(ssl: *mut openssl_sys::SSL) -> c_int;
+    fn SSL_has_pending(ssl: *const openssl_sys::SSL) -> c_int;
+    fn SSL_CTX_use_psk_identity_hint(ctx: *mut openssl_sys::SSL_CTX, hint: *const c_char) -> c_int;
+}
+
+/// Sets the callback computing the DTLS retransmission timeout in microseconds.
+///
+/// The callback is called with `0` when the timer is started, and with the previous timeout when
+/// the timer expired. This corresponds to `DTLS_set_timer_cb`, which `rust-openssl` does not
+/// expose.
+pub fn dtls_set_timer_callback(
+    ssl: &mut SslRef,
+    callback: unsafe extern "C" fn(*mut openssl_sys::SSL, c_uint) -> c_uint,
+) {
+    unsafe { DTLS_set_timer_cb(ssl.as_ptr(), Some(callback)) }
+}
+
+/// Sets the callback receiving handshake state changes and alerts.
+///
+/// This corresponds to `SSL_set_info_callback`, which `rust-openssl` only exposes for contexts.
+pub fn set_info_callback(ssl: &mut SslRef, callback: InfoCallback) {
+    unsafe { SSL_set_info_callback(ssl.as_ptr(), Some(callback)) }
+}
+
+/// Sets the callback receiving every protocol message sent and received.
+///
+/// This corresponds to `SSL_set_msg_callback`, which `rust-openssl` does not expose.
+pub fn set_msg_callback(ssl: &mut SslRef, callback: MsgCallback) {
+    unsafe { SSL_set_msg_callback(ssl.as_ptr(), Some(callback)) }
+}
+
+/// Returns the level (`warning` or `fatal`) of an alert value passed to the info callback.
+pub fn alert_type_string(value: c_int) -> &'static str {
+    unsafe { static_str(SSL_alert_type_string_long(value)) }
+}
+
+/// Returns the description of an alert value passed to the info callback.
+pub fn alert_desc_string(value: c_int) -> &'static str {
+    unsafe { static_str(SSL_alert_desc_string_long(value)) }
+}
+
+unsafe fn static_str(s: *const c_char) -> &'static str {
+    CStr::from_ptr(s).to_str().unwrap_or("unknown")
+}
+
 pub fn init_trust() {
     static ONCE: Once = Once::new();
     ONCE.call_once(|| openssl_probe::init_ssl_cert_env_vars());
 }
+
+/// Returns whether a close notify alert was received from the peer.
+///
+/// This corresponds to `SSL_get_shutdown`, which `rust-openssl` only exposes on a mutable
+/// `SslStream`.
+pub fn received_shutdown(ssl: &SslRef) -> bool {
+    unsafe { openssl_sys::SSL_get_shutdown(ssl.as_ptr()) & openssl_sys::SSL_RECEIVED_SHUTDOWN != 0 }
+}
+
+/// Returns whether the session buffered received data, processed or not, which a read returns
+/// without reading from the underlying stream.
+///
+/// This corresponds to `SSL_has_pending`, which `rust-openssl` does not expose.
+pub fn has_pending(ssl: &SslRef) -> bool {
+    unsafe { SSL_has_pending(ssl.as_ptr()) == 1 }
+}
+
+/// Sets the identity hint sent to clients authenticating with a pre-shared key.
+///
+/// This corresponds to `SSL_CTX_use_psk_identity_hint`, which `rust-openssl` does not expose.
+pub fn set_psk_identity_hint(ctx: &mut SslContextBuilder, hint: &[u8]) -> Result<(), Error> {
+    let hint = CString::new(hint)
+        .map_err(|_| Error::Configuration("PSK identity hint contains a NUL byte".to_string()))?;
+    if unsafe { SSL_CTX_use_psk_identity_hint(ctx.as_ptr(), hint.as_ptr()) } == 1 {
+        Ok(())
+    } else {
+        Err(ErrorStack::get().into())
+    }
+}
+
+/// Schedules a renegotiation, performed by the next read, write or handshake on the session.
+///
+/// This corresponds to `SSL_renegotiate`, which `rust-openssl` does not expose.
+pub fn renegotiate(ssl: &SslRef) -> Result<(), ErrorStack> {
+    if unsafe { SSL_renegotiate(ssl.as_ptr()) } == 1 {
+        Ok(())
+    } else {
+        Err(ErrorStack::get())
+    }
+}
+
+/// `SSL_OP_ALLOW_CLIENT_RENEGOTIATION`, introduced in OpenSSL 3.0 and not defined by `openssl-sys`.
+const SSL_OP_ALLOW_CLIENT_RENEGOTIATION: u64 = 0x100;
+
+/// Allows clients to initiate renegotiation, which OpenSSL 3.0 and newer refuse by default.
+///
+/// Older versions allow it already.
+pub fn allow_client_renegotiation(context: &mut SslContextBuilder) {
+    if openssl::version::number() >= 0x3000_0000 {
+        context.set_options(SslOptions::from_bits_retain(
+            SSL_OP_ALLOW_CLIENT_RENEGOTIATION as _,
+        ));
+    }
+}
@@ -1,8 +1,14 @@
 /// DTLS protocol versions.
+///
+/// With the `serde` feature, versions are deserialized from `"dtls1.0"` and `"dtls1.2"`.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub enum Protocol {
+    #[cfg_attr(feature = "serde", serde(rename = "dtls1.0"))]
     Dtlsv10,
+    #[cfg_attr(feature = "serde", serde(rename = "dtls1.2"))]
     Dtlsv12,
     #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     __NonExhaustive,
 }
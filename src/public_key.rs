@@ -0,0 +1,71 @@
+use crate::Error;
+use openssl::pkey::{PKey, Public};
+use std::fmt;
+
+/// A wrapper type for a public key, identified by its DER-encoded SubjectPublicKeyInfo.
+#[derive(Clone)]
+pub struct PublicKey(PKey<Public>);
+
+impl PublicKey {
+    /// Deserializes a DER-encoded SubjectPublicKeyInfo structure.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`d2i_PUBKEY`].
+    ///
+    /// [`d2i_PUBKEY`]: https://www.openssl.org/docs/manmaster/man3/d2i_PUBKEY.html
+    pub fn from_der(buf: &[u8]) -> Result<PublicKey, Error> {
+        let key = PKey::public_key_from_der(buf)?;
+        Ok(PublicKey(key))
+    }
+
+    /// Deserializes a PEM-encoded SubjectPublicKeyInfo structure.
+    ///
+    /// The input should have a header of `-----BEGIN PUBLIC KEY-----`.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`PEM_read_bio_PUBKEY`].
+    ///
+    /// [`PEM_read_bio_PUBKEY`]: https://www.openssl.org/docs/manmaster/man3/PEM_read_bio_PUBKEY.html
+    pub fn from_pem(buf: &[u8]) -> Result<PublicKey, Error> {
+        let key = PKey::public_key_from_pem(buf)?;
+        Ok(PublicKey(key))
+    }
+
+    /// Serializes the public key into a DER-encoded SubjectPublicKeyInfo structure.
+    ///
+    /// # Underlying SSL
+    /// This corresponds to [`i2d_PUBKEY`].
+    ///
+    /// [`i2d_PUBKEY`]: https://www.openssl.org/docs/manmaster/man3/i2d_PUBKEY.html
+    pub fn to_der(&self) -> Result<Vec<u8>, Error> {
+        let der = self.0.public_key_to_der()?;
+        Ok(der)
+    }
+}
+
+/// Keys are equal if they are of the same type and hold the same public components.
+impl PartialEq for PublicKey {
+    fn eq(&self, other: &PublicKey) -> bool {
+        self.0.public_eq(&other.0)
+    }
+}
+
+impl Eq for PublicKey {}
+
+impl fmt::Debug for PublicKey {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_tuple("PublicKey").field(&self.0.id()).finish()
+    }
+}
+
+impl From<PKey<Public>> for PublicKey {
+    fn from(key: PKey<Public>) -> Self {
+        PublicKey(key)
+    }
+}
+
+impl AsRef<PKey<Public>> for PublicKey {
+    fn as_ref(&self) -> &PKey<Public> {
+        &self.0
+    }
+}
@@ -0,0 +1,60 @@
+use crate::UdpChannel;
+use std::{io, net::UdpSocket, time::Duration};
+
+/// A stream whose blocking reads can be bounded by a timeout.
+///
+/// This is used to enforce handshake deadlines and to let OpenSSL retransmit lost handshake
+/// messages while a blocking stream waits for the peer.
+///
+/// Non-blocking streams can rely on the default implementation. Their handshake deadline is
+/// checked each time `MidHandshakeDtlsStream::handshake` is called.
+pub trait ReadTimeout {
+    /// Returns the read timeout of this stream.
+    ///
+    /// Returns an error of kind `io::ErrorKind::Unsupported` if reads can not be bounded, e.g.
+    /// because the stream is nonblocking.
+    fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Sets the read timeout of this stream.
+    ///
+    /// A read that times out has to return an error of kind `io::ErrorKind::WouldBlock`.
+    fn set_read_timeout(&mut self, _timeout: Option<Duration>) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// Reads of a nonblocking `UdpChannel` can not be bounded, they return right away.
+impl ReadTimeout for UdpChannel {
+    fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        if is_nonblocking(&self.socket)? {
+            return Err(io::ErrorKind::Unsupported.into());
+        }
+        self.socket.read_timeout()
+    }
+
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        if is_nonblocking(&self.socket)? {
+            return Err(io::ErrorKind::Unsupported.into());
+        }
+        self.socket.set_read_timeout(timeout)
+    }
+}
+
+/// Returns whether `socket` is in nonblocking mode.
+#[cfg(unix)]
+fn is_nonblocking(socket: &UdpSocket) -> io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+
+    match unsafe { libc::fcntl(socket.as_raw_fd(), libc::F_GETFL) } {
+        -1 => Err(io::Error::last_os_error()),
+        flags => Ok(flags & libc::O_NONBLOCK != 0),
+    }
+}
+
+/// Elsewhere, the mode of a socket can not be queried and it is assumed to be blocking.
+#[cfg(not(unix))]
+fn is_nonblocking(_socket: &UdpSocket) -> io::Result<bool> {
+    Ok(false)
+}
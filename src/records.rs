@@ -0,0 +1,236 @@
+use crate::callbacks;
+use libc::c_int;
+use openssl::{error::ErrorStack, ssl::SslRef};
+use std::{fmt, io, sync::Mutex};
+
+const SSL3_RT_CHANGE_CIPHER_SPEC: c_int = 20;
+const SSL3_RT_APPLICATION_DATA: c_int = 23;
+const SSL3_RT_HEADER: c_int = 0x100;
+
+/// The length of a DTLS record header: type, version, epoch, sequence number and length.
+const DTLS1_RT_HEADER_LENGTH: usize = 13;
+
+/// The number of records covered by the replay window, as used by OpenSSL.
+const REPLAY_WINDOW: u64 = 64;
+
+/// What a `DtlsStream` does when records are dropped as invalid.
+///
+/// OpenSSL always discards invalid records and keeps waiting for valid ones, as required by the
+/// DTLS specification. The policy only controls whether the drops are reported to the caller.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum InvalidRecordPolicy {
+    /// Invalid records are dropped silently and only counted in the `RecordStats`.
+    #[default]
+    Drop,
+    /// The first read after invalid records were dropped fails with an `io::Error` of kind
+    /// `io::ErrorKind::InvalidData`. A read that returned data still returns it, the error is
+    /// returned by the following read.
+    Error,
+}
+
+/// Statistics of the records dropped by a DTLS session, see `DtlsStream::record_stats`.
+///
+/// The counters are estimates. OpenSSL drops invalid records silently without reporting why, so
+/// they are derived from the record headers passed to the message callback, replaying OpenSSL's
+/// epoch and replay checks. Records dropped for any other reason, e.g. a malformed header or a
+/// record arriving before the handshake completed, are not counted, and a counter may be off
+/// when the estimate and OpenSSL disagree. The replay window covers the last 64 records of the
+/// current epoch, as in OpenSSL, and can not be configured.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub struct RecordStats {
+    /// Records dropped because their sequence number was already received, or is older than the
+    /// replay window.
+    pub replayed: u64,
+    /// Application data records which passed the epoch and replay checks, but were not returned
+    /// by a read. These are assumed to have failed authentication.
+    pub bad_mac: u64,
+    /// Records dropped because they belong to an epoch other than the current or the next one.
+    pub out_of_epoch: u64,
+}
+
+impl RecordStats {
+    /// Returns the number of dropped records.
+    pub fn dropped(&self) -> u64 {
+        self.replayed + self.bad_mac + self.out_of_epoch
+    }
+
+    fn since(&self, earlier: &RecordStats) -> RecordStats {
+        RecordStats {
+            replayed: self.replayed - earlier.replayed,
+            bad_mac: self.bad_mac - earlier.bad_mac,
+            out_of_epoch: self.out_of_epoch - earlier.out_of_epoch,
+        }
+    }
+}
+
+impl fmt::Display for RecordStats {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "{} records dropped ({} replayed, {} with a bad MAC, {} out of epoch)",
+            self.dropped(),
+            self.replayed,
+            self.bad_mac,
+            self.out_of_epoch
+        )
+    }
+}
+
+/// The replay window and drop statistics of a session.
+pub(crate) struct Records {
+    policy: InvalidRecordPolicy,
+    state: Mutex<RecordState>,
+}
+
+#[derive(Default)]
+struct RecordState {
+    read_epoch: u16,
+    /// The highest sequence number accepted in the current epoch, if any.
+    max_seq: Option<u64>,
+    /// Bit `n` is set if the record `max_seq - n` was accepted.
+    window: u64,
+    /// Application data records which passed the epoch and replay checks during the current read.
+    candidates: u64,
+    last_candidate: u64,
+    stats: RecordStats,
+    reported: RecordStats,
+}
+
+impl RecordState {
+    fn is_replay(&self, seq: u64) -> bool {
+        match self.max_seq {
+            Some(max_seq) if seq <= max_seq => {
+                let age = max_seq - seq;
+                age >= REPLAY_WINDOW || self.window & (1 << age) != 0
+            }
+            _ => false,
+        }
+    }
+
+    fn accept(&mut self, seq: u64) {
+        match self.max_seq {
+            Some(max_seq) if seq <= max_seq => self.window |= 1 << (max_seq - seq),
+            Some(max_seq) => {
+                let shift = seq - max_seq;
+                self.window = if shift >= REPLAY_WINDOW {
+                    1
+                } else {
+                    (self.window << shift) | 1
+                };
+                self.max_seq = Some(seq);
+            }
+            None => {
+                self.window = 1;
+                self.max_seq = Some(seq);
+            }
+        }
+    }
+}
+
+impl Records {
+    /// Attaches record tracking with the given policy to `ssl`.
+    pub(crate) fn start(policy: InvalidRecordPolicy, ssl: &mut SslRef) -> Result<(), ErrorStack> {
+        let records = Records {
+            policy,
+            state: Mutex::new(RecordState::default()),
+        };
+        callbacks::set_state(ssl, records)?;
+        Ok(())
+    }
+
+    /// Returns the record tracking attached to `ssl`, if any.
+    pub(crate) fn get(ssl: &SslRef) -> Option<&Records> {
+        callbacks::state(ssl)
+    }
+
+    /// Tracks a message passed to the message callback.
+    pub(crate) fn message(&self, sent: bool, content_type: c_int, message: &[u8]) {
+        if sent {
+            return;
+        }
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(_) => return,
+        };
+
+        match content_type {
+            SSL3_RT_HEADER if message.len() >= DTLS1_RT_HEADER_LENGTH => {
+                let record_type = c_int::from(message[0]);
+                let epoch = u16::from_be_bytes([message[3], message[4]]);
+                let seq = message[5..11]
+                    .iter()
+                    .fold(0, |seq, byte| (seq << 8) | u64::from(*byte));
+
+                if epoch == state.read_epoch.wrapping_add(1) {
+                    // Buffered by OpenSSL until the peer's ChangeCipherSpec is processed.
+                } else if epoch != state.read_epoch {
+                    state.stats.out_of_epoch += 1;
+                } else if state.is_replay(seq) {
+                    state.stats.replayed += 1;
+                } else if record_type == SSL3_RT_APPLICATION_DATA {
+                    // Only known to be authentic once OpenSSL returns its data.
+                    state.candidates += 1;
+                    state.last_candidate = seq;
+                } else {
+                    state.accept(seq);
+                }
+            }
+            SSL3_RT_CHANGE_CIPHER_SPEC => {
+                state.read_epoch = state.read_epoch.wrapping_add(1);
+                state.max_seq = None;
+                state.window = 0;
+                state.candidates = 0;
+            }
+            _ => {}
+        }
+    }
+
+    /// Settles the application data records seen during a read, given whether the read returned
+    /// data. All but the returned record were dropped for failing authentication.
+    pub(crate) fn read_done(&self, returned_data: bool) {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(_) => return,
+        };
+
+        let candidates = state.candidates;
+        if candidates == 0 {
+            return;
+        }
+        if returned_data {
+            let seq = state.last_candidate;
+            state.accept(seq);
+            state.stats.bad_mac += candidates - 1;
+        } else {
+            state.stats.bad_mac += candidates;
+        }
+        state.candidates = 0;
+    }
+
+    pub(crate) fn stats(&self) -> RecordStats {
+        self.state
+            .lock()
+            .map(|state| state.stats)
+            .unwrap_or_default()
+    }
+
+    /// Returns the error reporting the records dropped since the last error, if the policy asks
+    /// for it.
+    pub(crate) fn take_error(&self) -> Option<io::Error> {
+        if self.policy != InvalidRecordPolicy::Error {
+            return None;
+        }
+        let mut state = self.state.lock().ok()?;
+
+        let dropped = state.stats.since(&state.reported);
+        if dropped.dropped() == 0 {
+            return None;
+        }
+        state.reported = state.stats;
+        Some(io::Error::new(
+            io::ErrorKind::InvalidData,
+            dropped.to_string(),
+        ))
+    }
+}
@@ -0,0 +1,84 @@
+use crate::callbacks;
+use crate::openssl::renegotiate;
+use log::debug;
+use openssl::{error::ErrorStack, ssl::SslRef};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The traffic after which a session is renegotiated automatically.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct RekeyLimits {
+    pub(crate) bytes: Option<u64>,
+    pub(crate) records: Option<u64>,
+}
+
+impl RekeyLimits {
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.bytes.is_some() || self.records.is_some()
+    }
+}
+
+/// The completed handshakes of a session and its traffic since the last one.
+pub(crate) struct Rekey {
+    limits: RekeyLimits,
+    handshakes: AtomicU64,
+    bytes: AtomicU64,
+    records: AtomicU64,
+}
+
+impl Rekey {
+    /// Attaches handshake counting and automatic rekeying with the given limits to `ssl`.
+    pub(crate) fn start(limits: RekeyLimits, ssl: &mut SslRef) -> Result<(), ErrorStack> {
+        let rekey = Rekey {
+            limits,
+            handshakes: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            records: AtomicU64::new(0),
+        };
+        callbacks::set_state(ssl, rekey)?;
+        Ok(())
+    }
+
+    /// Returns the rekeying state attached to `ssl`, if any.
+    pub(crate) fn get(ssl: &SslRef) -> Option<&Rekey> {
+        callbacks::state(ssl)
+    }
+
+    /// Counts a record of `bytes` application data sent or received, and schedules a
+    /// renegotiation once a limit is reached.
+    ///
+    /// The renegotiation is performed by the next read or write on the stream.
+    pub(crate) fn record(&self, ssl: &SslRef, bytes: usize) {
+        if !self.limits.is_enabled() {
+            return;
+        }
+
+        let total_bytes = self.bytes.fetch_add(bytes as u64, Ordering::Relaxed) + bytes as u64;
+        let total_records = self.records.fetch_add(1, Ordering::Relaxed) + 1;
+
+        let limits = self.limits;
+        let exceeded = limits.bytes.is_some_and(|limit| total_bytes >= limit)
+            || limits.records.is_some_and(|limit| total_records >= limit);
+        if exceeded {
+            self.reset();
+            if let Err(err) = renegotiate(ssl) {
+                debug!("automatic renegotiation failed: {:?}", err);
+            }
+        }
+    }
+
+    /// Counts a completed handshake, called from the info callback.
+    pub(crate) fn handshake_done(&self) {
+        self.handshakes.fetch_add(1, Ordering::Relaxed);
+        self.reset();
+    }
+
+    /// Returns the number of renegotiations completed after the initial handshake.
+    pub(crate) fn renegotiations(&self) -> u64 {
+        self.handshakes.load(Ordering::Relaxed).saturating_sub(1)
+    }
+
+    fn reset(&self) {
+        self.bytes.store(0, Ordering::Relaxed);
+        self.records.store(0, Ordering::Relaxed);
+    }
+}
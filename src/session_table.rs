@@ -0,0 +1,252 @@
+use crate::session_table_builder::{CreatedCallback, EvictedCallback};
+use crate::{DtlsAcceptor, DtlsSession, Error, SessionTableBuilder};
+use std::{
+    collections::HashMap,
+    fmt,
+    net::{IpAddr, SocketAddr},
+    time::{Duration, Instant},
+};
+
+/// Why a `SessionTable` evicted a session.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum EvictionReason {
+    /// No datagram was received from the peer within the idle timeout.
+    Idle,
+    /// The maximum number of sessions was reached and the session was the least recently active.
+    Capacity,
+    /// The handshake failed.
+    HandshakeFailed,
+    /// The peer did not answer the keepalive probes.
+    Unresponsive,
+    /// The peer closed the session with a close notify alert.
+    Closed,
+}
+
+/// The `DtlsSession`s of a server, keyed by the address of their peer.
+///
+/// The server owns the socket, e.g. a `UdpBatch`, and passes each received datagram to
+/// `handle_datagram`, which creates the sessions of new peers with the `DtlsAcceptor`. The table
+/// tracks when each session last received a datagram, evicts idle sessions, limits the number of
+/// sessions and the rate of handshakes, and reports created and evicted sessions.
+///
+/// Call `handle_timeouts` when the duration returned by `next_timeout` elapsed, and send the
+/// datagrams of the sessions after each call, see `sessions_mut`.
+pub struct SessionTable {
+    acceptor: DtlsAcceptor,
+    sessions: HashMap<SocketAddr, Entry>,
+    idle_timeout: Option<Duration>,
+    max_sessions: Option<usize>,
+    handshake_rate: Option<(u32, Duration)>,
+    /// The start of the current rate limiting period and the handshakes started in it, per address.
+    handshakes: HashMap<IpAddr, (Instant, u32)>,
+    on_created: Option<CreatedCallback>,
+    on_evicted: Option<EvictedCallback>,
+}
+
+struct Entry {
+    session: DtlsSession,
+    last_received: Instant,
+}
+
+impl SessionTable {
+    /// Creates a `SessionTable` with the settings from the given builder.
+    pub fn new(builder: &SessionTableBuilder) -> SessionTable {
+        SessionTable {
+            acceptor: builder.acceptor.clone(),
+            sessions: HashMap::new(),
+            idle_timeout: builder.idle_timeout,
+            max_sessions: builder.max_sessions,
+            handshake_rate: builder.handshake_rate,
+            handshakes: HashMap::new(),
+            on_created: builder.on_created.clone(),
+            on_evicted: builder.on_evicted.clone(),
+        }
+    }
+
+    /// Returns a new builder for a `SessionTable` accepting sessions with `acceptor`.
+    pub fn builder(acceptor: DtlsAcceptor) -> SessionTableBuilder {
+        SessionTableBuilder {
+            acceptor,
+            idle_timeout: None,
+            max_sessions: None,
+            handshake_rate: None,
+            on_created: None,
+            on_evicted: None,
+        }
+    }
+
+    /// Passes a datagram received from `peer` to its session, creating the session if the peer
+    /// is new.
+    ///
+    /// Returns the session, or `None` if the datagram was dropped because the peer's address
+    /// exceeded the handshake rate limit. If the handshake fails, the session is evicted and the
+    /// error is returned.
+    pub fn handle_datagram(
+        &mut self,
+        peer: SocketAddr,
+        datagram: &[u8],
+    ) -> Result<Option<&mut DtlsSession>, Error> {
+        let now = Instant::now();
+
+        if !self.sessions.contains_key(&peer) {
+            if !self.allow_handshake(peer.ip(), now) {
+                return Ok(None);
+            }
+            if self
+                .max_sessions
+                .is_some_and(|max| self.sessions.len() >= max)
+            {
+                self.evict_least_recent();
+            }
+
+            let session = self.acceptor.accept_session()?;
+            if let Some(ref on_created) = self.on_created {
+                on_created(peer, &session);
+            }
+            let entry = Entry {
+                session,
+                last_received: now,
+            };
+            self.sessions.insert(peer, entry);
+        }
+
+        let entry = self.sessions.get_mut(&peer).expect("session was inserted");
+        entry.last_received = now;
+        if let Err(e) = entry.session.handle_datagram(datagram) {
+            self.evict(peer, EvictionReason::HandshakeFailed);
+            return Err(e);
+        }
+
+        Ok(self.sessions.get_mut(&peer).map(|entry| &mut entry.session))
+    }
+
+    /// Handles the expired retransmission and keepalive timers, and evicts the idle sessions and the ones the
+    /// peer closed.
+    ///
+    /// Returns the number of evicted sessions.
+    pub fn handle_timeouts(&mut self) -> usize {
+        let now = Instant::now();
+
+        let mut evicted = Vec::new();
+        for (peer, entry) in &mut self.sessions {
+            if entry.session.stream().peer_closed() {
+                evicted.push((*peer, EvictionReason::Closed));
+            } else if self
+                .idle_timeout
+                .is_some_and(|timeout| now.duration_since(entry.last_received) >= timeout)
+            {
+                evicted.push((*peer, EvictionReason::Idle));
+            } else if entry.session.timeout() == Some(Duration::from_secs(0)) {
+                match entry.session.handle_timeout() {
+                    Ok(_) => {}
+                    Err(Error::KeepaliveTimeout) => {
+                        evicted.push((*peer, EvictionReason::Unresponsive))
+                    }
+                    Err(_) => evicted.push((*peer, EvictionReason::HandshakeFailed)),
+                }
+            }
+        }
+        for (peer, reason) in &evicted {
+            self.evict(*peer, *reason);
+        }
+
+        if let Some((_, period)) = self.handshake_rate {
+            self.handshakes
+                .retain(|_, (start, _)| now.duration_since(*start) < period);
+        }
+
+        evicted.len()
+    }
+
+    /// Returns the time until `handle_timeouts` has work to do, if any session is subject to a
+    /// timeout.
+    pub fn next_timeout(&self) -> Option<Duration> {
+        let now = Instant::now();
+        self.sessions
+            .values()
+            .flat_map(|entry| {
+                let idle = self
+                    .idle_timeout
+                    .map(|timeout| (entry.last_received + timeout).saturating_duration_since(now));
+                idle.into_iter().chain(entry.session.timeout())
+            })
+            .min()
+    }
+
+    /// Returns the session of `peer`, if any.
+    pub fn get_mut(&mut self, peer: &SocketAddr) -> Option<&mut DtlsSession> {
+        self.sessions.get_mut(peer).map(|entry| &mut entry.session)
+    }
+
+    /// Removes the session of `peer` without reporting it as evicted.
+    pub fn remove(&mut self, peer: &SocketAddr) -> Option<DtlsSession> {
+        self.sessions.remove(peer).map(|entry| entry.session)
+    }
+
+    /// Returns all sessions with the addresses of their peers, e.g. to send their datagrams.
+    pub fn sessions_mut(&mut self) -> impl Iterator<Item = (SocketAddr, &mut DtlsSession)> {
+        self.sessions
+            .iter_mut()
+            .map(|(peer, entry)| (*peer, &mut entry.session))
+    }
+
+    /// Returns the number of sessions.
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Returns whether the table holds no sessions.
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Counts a handshake started by `ip`, returning whether the rate limit allows it.
+    fn allow_handshake(&mut self, ip: IpAddr, now: Instant) -> bool {
+        let (limit, period) = match self.handshake_rate {
+            Some(rate) => rate,
+            None => return true,
+        };
+
+        let (start, count) = self.handshakes.entry(ip).or_insert((now, 0));
+        if now.duration_since(*start) >= period {
+            *start = now;
+            *count = 0;
+        }
+        if *count >= limit {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+
+    fn evict_least_recent(&mut self) {
+        let least_recent = self
+            .sessions
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_received)
+            .map(|(peer, _)| *peer);
+        if let Some(peer) = least_recent {
+            self.evict(peer, EvictionReason::Capacity);
+        }
+    }
+
+    fn evict(&mut self, peer: SocketAddr, reason: EvictionReason) {
+        if let Some(entry) = self.sessions.remove(&peer) {
+            if let Some(ref on_evicted) = self.on_evicted {
+                on_evicted(peer, &entry.session, reason);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for SessionTable {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("SessionTable")
+            .field("sessions", &self.sessions.len())
+            .field("idle_timeout", &self.idle_timeout)
+            .field("max_sessions", &self.max_sessions)
+            .field("handshake_rate", &self.handshake_rate)
+            .finish()
+    }
+}
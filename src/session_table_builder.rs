@@ -0,0 +1,85 @@
+use crate::{DtlsAcceptor, DtlsSession, EvictionReason, SessionTable};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+pub(crate) type CreatedCallback = Arc<dyn Fn(SocketAddr, &DtlsSession) + Send + Sync>;
+pub(crate) type EvictedCallback =
+    Arc<dyn Fn(SocketAddr, &DtlsSession, EvictionReason) + Send + Sync>;
+
+/// A builder for `SessionTable`s.
+///
+/// With this builder you can configure the following properties:
+/// - Evicting sessions the peer stopped sending to
+/// - Limiting the number of sessions, evicting the least recently active one
+/// - Limiting the rate of handshakes per peer address
+/// - Observing created and evicted sessions
+pub struct SessionTableBuilder {
+    pub(crate) acceptor: DtlsAcceptor,
+    pub(crate) idle_timeout: Option<Duration>,
+    pub(crate) max_sessions: Option<usize>,
+    pub(crate) handshake_rate: Option<(u32, Duration)>,
+    pub(crate) on_created: Option<CreatedCallback>,
+    pub(crate) on_evicted: Option<EvictedCallback>,
+}
+
+impl SessionTableBuilder {
+    /// Evicts sessions which did not receive a datagram from their peer for `timeout`.
+    ///
+    /// Idle sessions are evicted by `SessionTable::handle_timeouts`.
+    ///
+    /// Defaults to `None`, sessions are never idle.
+    pub fn idle_timeout(&mut self, timeout: Option<Duration>) -> &mut SessionTableBuilder {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Limits the number of sessions.
+    ///
+    /// A datagram from a new peer evicts the session which least recently received a datagram
+    /// once the limit is reached.
+    ///
+    /// Defaults to `None`, the number of sessions is not limited.
+    pub fn max_sessions(&mut self, max: Option<usize>) -> &mut SessionTableBuilder {
+        self.max_sessions = max;
+        self
+    }
+
+    /// Limits the handshakes started by each IP address to `handshakes` per `period`.
+    ///
+    /// Datagrams from new peers are dropped while their address exceeds the limit. Ports are not
+    /// considered, so a peer can not evade the limit by changing its port.
+    ///
+    /// Defaults to `None`, handshakes are not limited.
+    pub fn handshake_rate_limit(
+        &mut self,
+        limit: Option<(u32, Duration)>,
+    ) -> &mut SessionTableBuilder {
+        self.handshake_rate = limit;
+        self
+    }
+
+    /// Sets a callback called with each new session, before its first datagram is handled.
+    pub fn on_session_created<F>(&mut self, callback: F) -> &mut SessionTableBuilder
+    where
+        F: Fn(SocketAddr, &DtlsSession) + Send + Sync + 'static,
+    {
+        self.on_created = Some(Arc::new(callback));
+        self
+    }
+
+    /// Sets a callback called with each session evicted by the table, and the reason of the
+    /// eviction.
+    ///
+    /// Sessions removed with `SessionTable::remove` are not reported.
+    pub fn on_session_evicted<F>(&mut self, callback: F) -> &mut SessionTableBuilder
+    where
+        F: Fn(SocketAddr, &DtlsSession, EvictionReason) + Send + Sync + 'static,
+    {
+        self.on_evicted = Some(Arc::new(callback));
+        self
+    }
+
+    /// Creates a new `SessionTable` with the settings from this builder.
+    pub fn build(&self) -> SessionTable {
+        SessionTable::new(self)
+    }
+}
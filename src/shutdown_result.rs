@@ -0,0 +1,19 @@
+use openssl::ssl;
+
+/// The state of a DTLS session after a call to `DtlsStream::shutdown`.
+#[derive(Hash, Clone, Copy, Eq, PartialEq, Debug)]
+pub enum ShutdownResult {
+    /// A close notify alert has been sent to the peer, its close notify is still outstanding.
+    Sent,
+    /// Close notify alerts have been exchanged in both directions, the session is fully shut down.
+    Received,
+}
+
+impl From<ssl::ShutdownResult> for ShutdownResult {
+    fn from(result: ssl::ShutdownResult) -> Self {
+        match result {
+            ssl::ShutdownResult::Sent => ShutdownResult::Sent,
+            ssl::ShutdownResult::Received => ShutdownResult::Received,
+        }
+    }
+}
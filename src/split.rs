@@ -0,0 +1,279 @@
+use crate::{DtlsStream, ReadTimeout, ShutdownResult, TryCloneSocket};
+use bytes::{Bytes, BytesMut};
+use std::{
+    fmt, io,
+    net::UdpSocket,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// The longest a blocking read holds the session while waiting for the peer.
+const READ_SLICE: Duration = Duration::from_millis(5);
+
+/// The session shared by the halves of a split `DtlsStream`.
+struct Shared<S> {
+    stream: Mutex<DtlsStream<S>>,
+    /// The number of writers waiting for the session, a reader lets them go first.
+    waiting_writers: Mutex<usize>,
+    /// Notified once no writer waits for the session anymore.
+    writers_done: Condvar,
+}
+
+impl<S> Shared<S> {
+    fn lock(&self) -> io::Result<MutexGuard<'_, DtlsStream<S>>> {
+        self.stream.lock().map_err(|_| poisoned())
+    }
+
+    fn lock_for_write(&self) -> io::Result<MutexGuard<'_, DtlsStream<S>>> {
+        *self.waiting_writers.lock().map_err(|_| poisoned())? += 1;
+        let stream = self.lock();
+
+        let mut waiting = self.waiting_writers.lock().map_err(|_| poisoned())?;
+        *waiting -= 1;
+        if *waiting == 0 {
+            self.writers_done.notify_all();
+        }
+        stream
+    }
+
+    /// Blocks until no writer waits for the session.
+    fn wait_for_writers(&self) -> io::Result<()> {
+        let mut waiting = self.waiting_writers.lock().map_err(|_| poisoned())?;
+        while *waiting > 0 {
+            waiting = self.writers_done.wait(waiting).map_err(|_| poisoned())?;
+        }
+        Ok(())
+    }
+}
+
+fn poisoned() -> io::Error {
+    io::Error::other("a thread panicked while using the DTLS session")
+}
+
+/// The receiving half of a `DtlsStream`, see `DtlsStream::split`.
+///
+/// Clones receive from the same session, each record is returned to one of them.
+pub struct ReadHalf<S> {
+    shared: Arc<Shared<S>>,
+    /// The socket to wait on for the peer without holding the session, if the stream has one.
+    socket: Option<Arc<UdpSocket>>,
+    read_timeout: Option<Duration>,
+}
+
+/// The sending half of a `DtlsStream`, see `DtlsStream::split`.
+///
+/// Clones send on the same session, their records are not interleaved.
+pub struct WriteHalf<S> {
+    shared: Arc<Shared<S>>,
+}
+
+/// Splits `stream` into halves sharing the session, see `DtlsStream::split`.
+pub(crate) fn split<S: io::Read + io::Write + ReadTimeout + TryCloneSocket>(
+    stream: DtlsStream<S>,
+) -> (ReadHalf<S>, WriteHalf<S>) {
+    let read_timeout = stream.get_ref().read_timeout().unwrap_or(None);
+    let socket = stream.get_ref().try_clone_socket().map(Arc::new);
+    let shared = Arc::new(Shared {
+        stream: Mutex::new(stream),
+        waiting_writers: Mutex::new(0),
+        writers_done: Condvar::new(),
+    });
+
+    let read = ReadHalf {
+        shared: shared.clone(),
+        socket,
+        read_timeout,
+    };
+    (read, WriteHalf { shared })
+}
+
+impl<S: io::Read + io::Write + ReadTimeout + TryCloneSocket> ReadHalf<S> {
+    /// Receives a single DTLS record into `buf`, see `DtlsStream::recv_datagram`.
+    pub fn recv_datagram(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_with(|stream| stream.recv_datagram(buf))
+    }
+
+    /// Receives a single DTLS record as `Bytes`, see `DtlsStream::recv_bytes`.
+    pub fn recv_bytes(&mut self, buf: &mut BytesMut) -> io::Result<Bytes> {
+        self.read_with(|stream| stream.recv_bytes(buf))
+    }
+
+    /// Returns the read timeout of this half, initially the one of the split stream.
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.read_timeout
+    }
+
+    /// Sets the read timeout of this half.
+    ///
+    /// A read that times out returns an error of kind `io::ErrorKind::WouldBlock`. `None` waits
+    /// for the peer indefinitely.
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    /// Locks the session, e.g. to call `DtlsStream::handle_timeout` or inspect the peer.
+    ///
+    /// Both halves wait while the lock is held.
+    pub fn lock(&self) -> io::Result<MutexGuard<'_, DtlsStream<S>>> {
+        self.shared.lock()
+    }
+
+    /// Runs `read` on the session until it returns something other than a timed out read, or
+    /// the read timeout passes.
+    ///
+    /// The peer is awaited on the cloned socket without holding the session. The underlying
+    /// stream's reads are bounded by `READ_SLICE` in any case, releasing the session to waiting
+    /// writers in between, and its read timeout is restored after each slice. Streams whose reads
+    /// can not be bounded, e.g. nonblocking ones, are read once.
+    fn read_with<T, F>(&mut self, mut read: F) -> io::Result<T>
+    where
+        F: FnMut(&mut DtlsStream<S>) -> io::Result<T>,
+    {
+        let deadline = self.read_timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            let slice = match deadline {
+                Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+                None => READ_SLICE,
+            };
+            if slice == Duration::from_secs(0) {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+
+            if let Some(ref socket) = self.socket {
+                if !self.shared.lock()?.has_pending() && !wait_readable(socket, deadline)? {
+                    continue;
+                }
+            }
+
+            self.shared.wait_for_writers()?;
+
+            let mut stream = self.shared.lock()?;
+            let bounded = Some(slice.min(READ_SLICE));
+            let previous = match stream.get_ref().read_timeout() {
+                Ok(previous) if stream.get_mut().set_read_timeout(bounded).is_ok() => {
+                    Some(previous)
+                }
+                _ => None,
+            };
+            let result = read(&mut stream);
+            if let Some(previous) = previous {
+                stream.get_mut().set_read_timeout(previous)?;
+            }
+            match result {
+                Err(ref e) if previous.is_some() && e.kind() == io::ErrorKind::WouldBlock => {}
+                result => return result,
+            }
+        }
+    }
+}
+
+impl<S: io::Read + io::Write + ReadTimeout + TryCloneSocket> io::Read for ReadHalf<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_with(|stream| stream.read(buf))
+    }
+}
+
+impl<S> Clone for ReadHalf<S> {
+    fn clone(&self) -> Self {
+        ReadHalf {
+            shared: self.shared.clone(),
+            socket: self.socket.clone(),
+            read_timeout: self.read_timeout,
+        }
+    }
+}
+
+impl<S> fmt::Debug for ReadHalf<S> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("ReadHalf")
+            .field("read_timeout", &self.read_timeout)
+            .finish()
+    }
+}
+
+impl<S: io::Read + io::Write> WriteHalf<S> {
+    /// Sends `buf` as a single DTLS record, see `DtlsStream::send_datagram`.
+    pub fn send_datagram(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.shared.lock_for_write()?.send_datagram(buf)
+    }
+
+    /// Sends `record` as a single DTLS record, see `DtlsStream::send_bytes`.
+    pub fn send_bytes(&mut self, record: Bytes) -> io::Result<()> {
+        self.shared.lock_for_write()?.send_bytes(record)
+    }
+
+    /// Sends a close notify message to the peer, see `DtlsStream::shutdown`.
+    pub fn shutdown(&mut self) -> io::Result<ShutdownResult> {
+        self.shared.lock_for_write()?.shutdown()
+    }
+
+    /// Locks the session, e.g. to call `DtlsStream::handle_timeout` or inspect the peer.
+    ///
+    /// Both halves wait while the lock is held.
+    pub fn lock(&self) -> io::Result<MutexGuard<'_, DtlsStream<S>>> {
+        self.shared.lock_for_write()
+    }
+}
+
+impl<S: io::Read + io::Write> io::Write for WriteHalf<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.shared.lock_for_write()?.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.shared.lock_for_write()?.flush()
+    }
+}
+
+impl<S> Clone for WriteHalf<S> {
+    fn clone(&self) -> Self {
+        WriteHalf {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<S> fmt::Debug for WriteHalf<S> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("WriteHalf").finish()
+    }
+}
+
+/// Waits until `socket` received a datagram or `deadline` passes, returning whether it received
+/// one. Interrupted waits return `false` as well.
+#[cfg(unix)]
+fn wait_readable(socket: &UdpSocket, deadline: Option<Instant>) -> io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+
+    let timeout = match deadline {
+        Some(deadline) => {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            remaining
+                .as_nanos()
+                .div_ceil(1_000_000)
+                .min(libc::c_int::MAX as u128) as libc::c_int
+        }
+        None => -1,
+    };
+    let mut fd = libc::pollfd {
+        fd: socket.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    match unsafe { libc::poll(&mut fd, 1, timeout) } {
+        -1 => {
+            let e = io::Error::last_os_error();
+            if e.kind() == io::ErrorKind::Interrupted {
+                return Ok(false);
+            }
+            Err(e)
+        }
+        ready => Ok(ready > 0),
+    }
+}
+
+/// Elsewhere, the session is held in short slices while waiting for the peer.
+#[cfg(not(unix))]
+fn wait_readable(_socket: &UdpSocket, _deadline: Option<Instant>) -> io::Result<bool> {
+    Ok(true)
+}
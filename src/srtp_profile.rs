@@ -29,3 +29,17 @@ impl ToString for SrtpProfile {
         .to_string()
     }
 }
+
+/// Deserializes a profile from its name, e.g. `"SRTP_AES128_CM_SHA1_80"`.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SrtpProfile {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(|_| {
+            serde::de::Error::invalid_value(
+                serde::de::Unexpected::Str(&name),
+                &"an SRTP profile name such as `SRTP_AES128_CM_SHA1_80`",
+            )
+        })
+    }
+}
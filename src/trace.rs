@@ -0,0 +1,144 @@
+use crate::callbacks;
+use crate::openssl::{alert_desc_string, alert_type_string};
+use crate::Role;
+use libc::{c_int, c_uint};
+use log::debug;
+use openssl::{error::ErrorStack, ssl::SslRef};
+use std::{fmt, net::SocketAddr};
+
+/// The log target of the handshake trace.
+const TARGET: &str = "udp_dtls::trace";
+
+const SSL_CB_LOOP: c_int = 0x01;
+const SSL_CB_EXIT: c_int = 0x02;
+const SSL_CB_READ: c_int = 0x04;
+const SSL_CB_ALERT: c_int = 0x4000;
+const SSL_CB_HANDSHAKE_START: c_int = 0x10;
+const SSL_CB_HANDSHAKE_DONE: c_int = 0x20;
+
+const SSL3_RT_CHANGE_CIPHER_SPEC: c_int = 20;
+const SSL3_RT_HANDSHAKE: c_int = 22;
+
+/// Handshake tracing of a session.
+///
+/// Every event is logged at debug level with the `udp_dtls::trace` target, labeled with the role
+/// and peer address of the session.
+pub(crate) struct Trace {
+    role: Role,
+    peer: Option<SocketAddr>,
+}
+
+impl Trace {
+    /// Attaches tracing to `ssl` and installs the callbacks reporting to it.
+    pub(crate) fn start(
+        role: Role,
+        peer: Option<SocketAddr>,
+        ssl: &mut SslRef,
+    ) -> Result<(), ErrorStack> {
+        let trace = Trace { role, peer };
+        debug!(target: TARGET, "{}: starting handshake", trace);
+
+        callbacks::set_state(ssl, trace)?;
+        callbacks::install_trace_callbacks(ssl);
+        callbacks::install_timer_callback(ssl);
+        Ok(())
+    }
+
+    /// Returns the trace attached to `ssl`, if any.
+    pub(crate) fn get(ssl: &SslRef) -> Option<&Trace> {
+        callbacks::state(ssl)
+    }
+
+    /// Logs a state change or alert reported by the info callback.
+    pub(crate) fn info(&self, ssl: &SslRef, where_: c_int, value: c_int) {
+        if where_ & SSL_CB_ALERT != 0 {
+            let direction = if where_ & SSL_CB_READ != 0 {
+                "received"
+            } else {
+                "sent"
+            };
+            debug!(
+                target: TARGET,
+                "{}: {} {} alert: {}",
+                self,
+                direction,
+                alert_type_string(value),
+                alert_desc_string(value)
+            );
+        } else if where_ & SSL_CB_HANDSHAKE_START != 0 {
+            debug!(target: TARGET, "{}: handshake started", self);
+        } else if where_ & SSL_CB_HANDSHAKE_DONE != 0 {
+            debug!(target: TARGET, "{}: handshake done", self);
+        } else if where_ & SSL_CB_LOOP != 0 {
+            debug!(target: TARGET, "{}: state {}", self, ssl.state_string_long());
+        } else if where_ & SSL_CB_EXIT != 0 && value == 0 {
+            debug!(
+                target: TARGET,
+                "{}: failed in state {}",
+                self,
+                ssl.state_string_long()
+            );
+        }
+    }
+
+    /// Logs a handshake or change cipher spec message reported by the message callback.
+    pub(crate) fn message(&self, sent: bool, content_type: c_int, message: &[u8]) {
+        let name = match content_type {
+            SSL3_RT_HANDSHAKE => match message.first() {
+                Some(&message_type) => handshake_message_name(message_type),
+                None => return,
+            },
+            SSL3_RT_CHANGE_CIPHER_SPEC => "ChangeCipherSpec",
+            _ => return,
+        };
+
+        let direction = if sent { "sent" } else { "received" };
+        debug!(
+            target: TARGET,
+            "{}: {} {} ({} bytes)",
+            self,
+            direction,
+            name,
+            message.len()
+        );
+    }
+
+    /// Logs the retransmission of the last flight.
+    pub(crate) fn retransmission(&self, next_timeout_us: c_uint) {
+        debug!(
+            target: TARGET,
+            "{}: retransmitting last flight, next timeout {} ms",
+            self,
+            next_timeout_us / 1000
+        );
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.peer {
+            Some(peer) => write!(fmt, "{} {}", self.role, peer),
+            None => write!(fmt, "{} <unknown peer>", self.role),
+        }
+    }
+}
+
+/// Returns the name of a handshake message type as defined in RFC6347.
+fn handshake_message_name(message_type: u8) -> &'static str {
+    match message_type {
+        0 => "HelloRequest",
+        1 => "ClientHello",
+        2 => "ServerHello",
+        3 => "HelloVerifyRequest",
+        4 => "NewSessionTicket",
+        11 => "Certificate",
+        12 => "ServerKeyExchange",
+        13 => "CertificateRequest",
+        14 => "ServerHelloDone",
+        15 => "CertificateVerify",
+        16 => "ClientKeyExchange",
+        20 => "Finished",
+        22 => "CertificateStatus",
+        _ => "unknown handshake message",
+    }
+}
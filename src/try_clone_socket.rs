@@ -0,0 +1,20 @@
+use crate::UdpChannel;
+use std::net::UdpSocket;
+
+/// A stream backed by a socket which can be waited on without borrowing the stream.
+///
+/// The `ReadHalf` of a split `DtlsStream` waits on the cloned socket for the peer, so writes are
+/// not held up by a blocked read. Streams without a socket can rely on the default
+/// implementation, their reads hold the session in short slices instead.
+pub trait TryCloneSocket {
+    /// Returns a clone of the underlying socket, if the stream has one.
+    fn try_clone_socket(&self) -> Option<UdpSocket> {
+        None
+    }
+}
+
+impl TryCloneSocket for UdpChannel {
+    fn try_clone_socket(&self) -> Option<UdpSocket> {
+        self.socket.try_clone().ok()
+    }
+}
@@ -0,0 +1,295 @@
+use crate::DtlsSession;
+use std::{
+    fmt, io,
+    net::{SocketAddr, UdpSocket},
+};
+
+/// The size of each receive buffer, the largest UDP payload.
+const BUFFER_SIZE: usize = 65_535;
+
+/// A UDP socket receiving and sending several datagrams per system call, for servers talking to
+/// many peers through `DtlsSession`s.
+///
+/// On Linux, `recv` uses one `recvmmsg` and `flush` one `sendmmsg` call per `batch_size`
+/// datagrams. Elsewhere, they fall back to one `recv_from` or `send_to` call per datagram.
+///
+/// Segmentation offloads (`UDP_SEGMENT` and `UDP_GRO`) are not used: they require the datagrams of
+/// a batch to be of equal size and go to the same peer, which DTLS records rarely are.
+pub struct UdpBatch {
+    socket: UdpSocket,
+    buffers: Vec<Vec<u8>>,
+    /// The index of the buffer, the length and the sender of each received datagram.
+    received: Vec<(usize, usize, SocketAddr)>,
+    outgoing: Vec<(SocketAddr, Vec<u8>)>,
+}
+
+impl UdpBatch {
+    /// Creates a batch of `batch_size` datagrams on the given socket, at least one.
+    ///
+    /// Reserves a buffer of 64 KiB per datagram for receiving.
+    pub fn new(socket: UdpSocket, batch_size: usize) -> UdpBatch {
+        let batch_size = batch_size.max(1);
+        UdpBatch {
+            socket,
+            buffers: vec![vec![0; BUFFER_SIZE]; batch_size],
+            received: Vec::with_capacity(batch_size),
+            outgoing: Vec::new(),
+        }
+    }
+
+    /// Returns the underlying socket.
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// Receives up to `batch_size` datagrams, returning how many were received.
+    ///
+    /// Waits for the first datagram as configured on the socket, e.g. fails with
+    /// `io::ErrorKind::WouldBlock` on a nonblocking socket, and then takes the datagrams which
+    /// are already queued. The datagrams are available with `received` until the next call.
+    pub fn recv(&mut self) -> io::Result<usize> {
+        self.received.clear();
+        sys::recv(&self.socket, &mut self.buffers, &mut self.received)?;
+        Ok(self.received.len())
+    }
+
+    /// Returns the datagrams received by the last `recv`, with their senders.
+    pub fn received(&self) -> impl Iterator<Item = (SocketAddr, &[u8])> {
+        self.received
+            .iter()
+            .map(move |&(index, len, peer)| (peer, &self.buffers[index][..len]))
+    }
+
+    /// Queues a datagram to send to `peer` with the next `flush`.
+    pub fn queue(&mut self, peer: SocketAddr, datagram: Vec<u8>) {
+        self.outgoing.push((peer, datagram));
+    }
+
+    /// Queues all datagrams the session has to send to `peer`, returning how many were queued.
+    pub fn queue_session(&mut self, peer: SocketAddr, session: &mut DtlsSession) -> usize {
+        let queued = self.outgoing.len();
+        while let Some(datagram) = session.poll_datagram() {
+            self.outgoing.push((peer, datagram));
+        }
+        self.outgoing.len() - queued
+    }
+
+    /// Returns the number of queued datagrams.
+    pub fn pending(&self) -> usize {
+        self.outgoing.len()
+    }
+
+    /// Sends the queued datagrams, `batch_size` per system call, returning how many were sent.
+    ///
+    /// Datagrams which could not be sent, e.g. because a nonblocking socket would block, stay
+    /// queued for the next `flush`. An error is only returned if no datagram was sent.
+    pub fn flush(&mut self) -> io::Result<usize> {
+        let mut sent = 0;
+        while sent < self.outgoing.len() {
+            let end = self.outgoing.len().min(sent + self.buffers.len());
+            match sys::send(&self.socket, &self.outgoing[sent..end]) {
+                Ok(0) => break,
+                Ok(count) => sent += count,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    if sent == 0 {
+                        return Err(e);
+                    }
+                    break;
+                }
+            }
+        }
+        self.outgoing.drain(..sent);
+        Ok(sent)
+    }
+}
+
+impl fmt::Debug for UdpBatch {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("UdpBatch")
+            .field("socket", &self.socket)
+            .field("batch_size", &self.buffers.len())
+            .field("received", &self.received.len())
+            .field("pending", &self.outgoing.len())
+            .finish()
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use libc::{c_uint, iovec, mmsghdr, sockaddr_in, sockaddr_in6, sockaddr_storage, socklen_t};
+    use std::{
+        io, mem,
+        net::{SocketAddr, SocketAddrV4, SocketAddrV6, UdpSocket},
+        os::unix::io::AsRawFd,
+        ptr,
+    };
+
+    pub(super) fn recv(
+        socket: &UdpSocket,
+        buffers: &mut [Vec<u8>],
+        received: &mut Vec<(usize, usize, SocketAddr)>,
+    ) -> io::Result<()> {
+        let mut iovecs = buffers
+            .iter_mut()
+            .map(|buffer| iovec {
+                iov_base: buffer.as_mut_ptr() as *mut _,
+                iov_len: buffer.len(),
+            })
+            .collect::<Vec<_>>();
+        let mut addrs = vec![unsafe { mem::zeroed::<sockaddr_storage>() }; buffers.len()];
+        let mut messages = iovecs
+            .iter_mut()
+            .zip(&mut addrs)
+            .map(|(iovec, addr)| {
+                let mut message = unsafe { mem::zeroed::<mmsghdr>() };
+                message.msg_hdr.msg_name = addr as *mut _ as *mut _;
+                message.msg_hdr.msg_namelen = mem::size_of::<sockaddr_storage>() as socklen_t;
+                message.msg_hdr.msg_iov = iovec;
+                message.msg_hdr.msg_iovlen = 1;
+                message
+            })
+            .collect::<Vec<_>>();
+
+        let count = unsafe {
+            libc::recvmmsg(
+                socket.as_raw_fd(),
+                messages.as_mut_ptr(),
+                messages.len() as c_uint,
+                libc::MSG_WAITFORONE,
+                ptr::null_mut(),
+            )
+        };
+        if count < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let messages = messages.iter().zip(&addrs).take(count as usize);
+        for (index, (message, addr)) in messages.enumerate() {
+            if let Some(peer) = socket_addr(addr) {
+                received.push((index, message.msg_len as usize, peer));
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn send(
+        socket: &UdpSocket,
+        datagrams: &[(SocketAddr, Vec<u8>)],
+    ) -> io::Result<usize> {
+        let mut addrs = datagrams
+            .iter()
+            .map(|(peer, _)| raw_socket_addr(peer))
+            .collect::<Vec<_>>();
+        let mut iovecs = datagrams
+            .iter()
+            .map(|(_, datagram)| iovec {
+                iov_base: datagram.as_ptr() as *mut _,
+                iov_len: datagram.len(),
+            })
+            .collect::<Vec<_>>();
+        let mut messages = iovecs
+            .iter_mut()
+            .zip(&mut addrs)
+            .map(|(iovec, (addr, len))| {
+                let mut message = unsafe { mem::zeroed::<mmsghdr>() };
+                message.msg_hdr.msg_name = addr as *mut _ as *mut _;
+                message.msg_hdr.msg_namelen = *len;
+                message.msg_hdr.msg_iov = iovec;
+                message.msg_hdr.msg_iovlen = 1;
+                message
+            })
+            .collect::<Vec<_>>();
+
+        let count = unsafe {
+            libc::sendmmsg(
+                socket.as_raw_fd(),
+                messages.as_mut_ptr(),
+                messages.len() as c_uint,
+                0,
+            )
+        };
+        if count < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(count as usize)
+    }
+
+    fn socket_addr(addr: &sockaddr_storage) -> Option<SocketAddr> {
+        match addr.ss_family as libc::c_int {
+            libc::AF_INET => {
+                let addr = unsafe { &*(addr as *const _ as *const sockaddr_in) };
+                Some(SocketAddr::V4(SocketAddrV4::new(
+                    u32::from_be(addr.sin_addr.s_addr).into(),
+                    u16::from_be(addr.sin_port),
+                )))
+            }
+            libc::AF_INET6 => {
+                let addr = unsafe { &*(addr as *const _ as *const sockaddr_in6) };
+                Some(SocketAddr::V6(SocketAddrV6::new(
+                    addr.sin6_addr.s6_addr.into(),
+                    u16::from_be(addr.sin6_port),
+                    addr.sin6_flowinfo,
+                    addr.sin6_scope_id,
+                )))
+            }
+            _ => None,
+        }
+    }
+
+    fn raw_socket_addr(peer: &SocketAddr) -> (sockaddr_storage, socklen_t) {
+        let mut storage = unsafe { mem::zeroed::<sockaddr_storage>() };
+        let len = match peer {
+            SocketAddr::V4(peer) => {
+                let addr = unsafe { &mut *(&mut storage as *mut _ as *mut sockaddr_in) };
+                addr.sin_family = libc::AF_INET as libc::sa_family_t;
+                addr.sin_port = peer.port().to_be();
+                addr.sin_addr.s_addr = u32::from(*peer.ip()).to_be();
+                mem::size_of::<sockaddr_in>()
+            }
+            SocketAddr::V6(peer) => {
+                let addr = unsafe { &mut *(&mut storage as *mut _ as *mut sockaddr_in6) };
+                addr.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+                addr.sin6_port = peer.port().to_be();
+                addr.sin6_addr.s6_addr = peer.ip().octets();
+                addr.sin6_flowinfo = peer.flowinfo();
+                addr.sin6_scope_id = peer.scope_id();
+                mem::size_of::<sockaddr_in6>()
+            }
+        };
+        (storage, len as socklen_t)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use std::{
+        io,
+        net::{SocketAddr, UdpSocket},
+    };
+
+    pub(super) fn recv(
+        socket: &UdpSocket,
+        buffers: &mut [Vec<u8>],
+        received: &mut Vec<(usize, usize, SocketAddr)>,
+    ) -> io::Result<()> {
+        let (len, peer) = socket.recv_from(&mut buffers[0])?;
+        received.push((0, len, peer));
+        Ok(())
+    }
+
+    pub(super) fn send(
+        socket: &UdpSocket,
+        datagrams: &[(SocketAddr, Vec<u8>)],
+    ) -> io::Result<usize> {
+        let mut sent = 0;
+        for (peer, datagram) in datagrams {
+            match socket.send_to(datagram, peer) {
+                Ok(_) => sent += 1,
+                Err(e) if sent == 0 => return Err(e),
+                Err(_) => break,
+            }
+        }
+        Ok(sent)
+    }
+}
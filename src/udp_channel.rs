@@ -1,4 +1,4 @@
-use std::io::{Error, Read, Result, Write};
+use std::io::{Error, ErrorKind, Read, Result, Write};
 use std::net::{SocketAddr, UdpSocket};
 use std::result;
 
@@ -11,7 +11,11 @@ pub struct UdpChannel {
 
 impl Read for UdpChannel {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
-        self.socket.recv(buf)
+        match self.socket.recv(buf) {
+            // a timed out read is reported as `TimedOut` on some platforms, OpenSSL only retries on `WouldBlock`
+            Err(ref e) if e.kind() == ErrorKind::TimedOut => Err(ErrorKind::WouldBlock.into()),
+            result => result,
+        }
     }
 }
 
@@ -0,0 +1,36 @@
+mod common;
+
+use udp_dtls::{AlertDescription, AlertLevel, DtlsAcceptor, DtlsConnector, MAX_DATAGRAM_SIZE};
+
+#[test]
+fn close_notify_is_reported() {
+    let acceptor = DtlsAcceptor::builder(common::identity()).build().unwrap();
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .build()
+        .unwrap();
+
+    let (server, client) = common::handshake(acceptor, connector);
+    let (mut server, mut client) = (server.unwrap(), client.unwrap());
+    assert_eq!(server.received_alert(), None);
+
+    client.shutdown().unwrap();
+
+    let mut buf = [0; MAX_DATAGRAM_SIZE];
+    assert_eq!(server.recv_datagram(&mut buf).unwrap(), 0);
+
+    let alert = server.received_alert().unwrap();
+    assert!(alert.is_close_notify());
+    assert!(!alert.is_fatal());
+    assert_eq!(alert.level(), AlertLevel::Warning);
+    assert_eq!(client.sent_alert(), Some(alert));
+}
+
+#[test]
+fn descriptions_round_trip() {
+    for code in 0..=255 {
+        assert_eq!(AlertDescription::from_code(code).code(), code);
+    }
+    assert_eq!(AlertDescription::from_code(48), AlertDescription::UnknownCa);
+    assert_eq!(AlertDescription::from_code(255), AlertDescription::Other(255));
+}
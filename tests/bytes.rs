@@ -0,0 +1,46 @@
+mod common;
+
+use bytes::{Bytes, BytesMut};
+use udp_dtls::{DtlsAcceptor, DtlsConnector, MAX_DATAGRAM_SIZE};
+
+#[test]
+fn records_are_exchanged_as_bytes() {
+    let acceptor = DtlsAcceptor::builder(common::identity()).build().unwrap();
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .build()
+        .unwrap();
+    let (server, client) = common::handshake(acceptor, connector);
+    let (mut server, mut client) = (server.unwrap(), client.unwrap());
+
+    client.send_bytes(Bytes::from_static(b"first")).unwrap();
+    client.send_bytes(Bytes::from_static(b"second")).unwrap();
+
+    let mut buf = BytesMut::with_capacity(4 * MAX_DATAGRAM_SIZE);
+    let first = server.recv_bytes(&mut buf).unwrap();
+    let second = server.recv_bytes(&mut buf).unwrap();
+    assert_eq!(&first[..], b"first");
+    assert_eq!(&second[..], b"second");
+    assert!(buf.is_empty());
+    // the records share the reserved allocation
+    assert!(buf.capacity() >= 2 * MAX_DATAGRAM_SIZE);
+
+    client.shutdown().unwrap();
+    assert!(server.recv_bytes(&mut buf).unwrap().is_empty());
+}
+
+#[test]
+fn oversized_records_are_refused() {
+    let acceptor = DtlsAcceptor::builder(common::identity()).build().unwrap();
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .build()
+        .unwrap();
+    let (_, client) = common::handshake(acceptor, connector);
+
+    let error = client
+        .unwrap()
+        .send_bytes(Bytes::from(vec![0; MAX_DATAGRAM_SIZE + 1]))
+        .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use udp_dtls::{CipherPreset, DtlsAcceptor, DtlsConnector, PskIdentity};
+
+#[test]
+fn restricted_acceptor_cipher_list_is_negotiated() {
+    let acceptor = DtlsAcceptor::builder(common::identity())
+        .add_cipher("ECDHE-ECDSA-AES256-GCM-SHA384")
+        .add_group("P-384")
+        .build()
+        .unwrap();
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .build()
+        .unwrap();
+
+    let (server, client) = common::handshake(acceptor, connector);
+    let (server, _client) = (server.unwrap(), client.unwrap());
+
+    let cipher = server.as_ref().ssl().current_cipher().unwrap();
+    assert_eq!(cipher.name(), "ECDHE-ECDSA-AES256-GCM-SHA384");
+}
+
+#[test]
+fn psk_only_preset_on_both_ends() {
+    let identity = PskIdentity::new(b"client", b"0123456789abcdef");
+
+    let acceptor = DtlsAcceptor::builder(common::identity())
+        .cipher_preset(CipherPreset::PskOnly)
+        .add_psk_identity(identity.clone())
+        .build()
+        .unwrap();
+    let connector = DtlsConnector::builder()
+        .cipher_preset(CipherPreset::PskOnly)
+        .identity(udp_dtls::ConnectorIdentity::Psk(identity))
+        .build()
+        .unwrap();
+
+    let (server, client) = common::handshake(acceptor, connector);
+    let (server, _client) = (server.unwrap(), client.unwrap());
+
+    let cipher = server.as_ref().ssl().current_cipher().unwrap();
+    assert!(cipher.name().contains("PSK"));
+}
+
+#[test]
+fn unknown_names_fail_to_build() {
+    assert!(DtlsConnector::builder().add_cipher("NOT-A-CIPHER").build().is_err());
+    assert!(DtlsConnector::builder().add_group("not-a-group").build().is_err());
+    assert!(DtlsConnector::builder()
+        .add_signature_algorithm("NOT+ALG")
+        .build()
+        .is_err());
+
+    assert!(DtlsAcceptor::builder(common::identity())
+        .add_cipher("NOT-A-CIPHER")
+        .build()
+        .is_err());
+    assert!(DtlsAcceptor::builder(common::identity())
+        .add_group("not-a-group")
+        .build()
+        .is_err());
+    assert!(DtlsAcceptor::builder(common::identity())
+        .add_signature_algorithm("NOT+ALG")
+        .build()
+        .is_err());
+}
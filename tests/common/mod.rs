@@ -0,0 +1,93 @@
+#![allow(dead_code)]
+
+use std::{
+    fmt,
+    net::UdpSocket,
+    thread,
+    time::{Duration, Instant},
+};
+use udp_dtls::{
+    Certificate, CertificateIdentity, DtlsAcceptor, DtlsConnector, DtlsStream, Error,
+    HandshakeError, UdpChannel,
+};
+
+pub fn identity() -> CertificateIdentity {
+    CertificateIdentity::from_pkcs12(include_bytes!("../../test/server.p12"), "mypass").unwrap()
+}
+
+pub fn root_certificate() -> Certificate {
+    Certificate::from_der(include_bytes!("../../test/server.der")).unwrap()
+}
+
+/// Returns an identity for `foobar.com` issued by `test_ca`, which signs the CRLs and OCSP
+/// responses in `test/`.
+pub fn leaf_identity() -> CertificateIdentity {
+    CertificateIdentity::from_pkcs12(include_bytes!("../../test/leaf.p12"), "mypass").unwrap()
+}
+
+pub fn test_ca() -> Certificate {
+    Certificate::from_pem(include_bytes!("../../test/ca.pem")).unwrap()
+}
+
+/// Returns two `UdpChannel`s on the loopback interface that talk to each other.
+pub fn channel_pair() -> (UdpChannel, UdpChannel) {
+    let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+    let server_addr = server.local_addr().unwrap();
+    let client_addr = client.local_addr().unwrap();
+
+    (
+        UdpChannel {
+            socket: server,
+            remote_addr: client_addr,
+        },
+        UdpChannel {
+            socket: client,
+            remote_addr: server_addr,
+        },
+    )
+}
+
+/// Performs a handshake between the given acceptor and connector over loopback.
+pub fn handshake(
+    acceptor: DtlsAcceptor,
+    connector: DtlsConnector,
+) -> (
+    Result<DtlsStream<UdpChannel>, Error>,
+    Result<DtlsStream<UdpChannel>, Error>,
+) {
+    handshake_to(acceptor, connector, "foobar.com")
+}
+
+/// The longest a test handshake may take, so a stalled handshake fails the test.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Performs a handshake between the given acceptor and connector over loopback, connecting to
+/// the given domain.
+pub fn handshake_to(
+    acceptor: DtlsAcceptor,
+    connector: DtlsConnector,
+    domain: &str,
+) -> (
+    Result<DtlsStream<UdpChannel>, Error>,
+    Result<DtlsStream<UdpChannel>, Error>,
+) {
+    let (server_channel, client_channel) = channel_pair();
+
+    let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+    let server =
+        thread::spawn(move || failure(acceptor.accept_with_deadline(server_channel, deadline)));
+    let client = failure(connector.connect_with_deadline(domain, client_channel, deadline));
+
+    (server.join().unwrap(), client)
+}
+
+fn failure<S: fmt::Debug>(
+    result: Result<DtlsStream<S>, HandshakeError<S>>,
+) -> Result<DtlsStream<S>, Error> {
+    result.map_err(|e| match e {
+        HandshakeError::Failure(e) => e,
+        HandshakeError::WouldBlock(_) => panic!("handshake on a blocking socket would block"),
+    })
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "serde")]
+
+mod common;
+
+use udp_dtls::{AcceptorConfig, ConnectorConfig, Error};
+
+fn acceptor_config(password: &str) -> AcceptorConfig {
+    toml::from_str(&format!(
+        r#"
+            min_protocol = "dtls1.2"
+            srtp_profiles = ["SRTP_AES128_CM_SHA1_80"]
+
+            [identity]
+            pkcs12 = "test/server.p12"
+            password = {}
+        "#,
+        password
+    ))
+    .unwrap()
+}
+
+fn configuration_error(result: udp_dtls::Result<impl Sized>) -> String {
+    match result {
+        Err(Error::Configuration(message)) => message,
+        Err(e) => panic!("expected a configuration error, got {:?}", e),
+        Ok(_) => panic!("expected a configuration error"),
+    }
+}
+
+#[test]
+fn configured_endpoints_complete_handshake() {
+    let acceptor = acceptor_config(r#"{ value = "mypass" }"#)
+        .builder()
+        .unwrap()
+        .build()
+        .unwrap();
+    let connector: ConnectorConfig = toml::from_str(
+        r#"
+            min_protocol = "dtls1.2"
+            max_protocol = "dtls1.2"
+            srtp_profiles = ["SRTP_AES128_CM_SHA1_80"]
+            root_certificates = ["test/server.der"]
+            use_sni = true
+        "#,
+    )
+    .unwrap();
+    let connector = connector.builder().unwrap().build().unwrap();
+
+    let (server, client) = common::handshake(acceptor, connector);
+    let client = client.unwrap();
+    server.unwrap();
+    assert!(client.selected_srtp_profile().unwrap().is_some());
+}
+
+#[test]
+fn unknown_keys_are_rejected() {
+    let error = toml::from_str::<ConnectorConfig>("use_snii = false").unwrap_err();
+    assert!(error.to_string().contains("use_snii"), "{}", error);
+}
+
+#[test]
+fn invalid_values_name_the_key() {
+    let error = toml::from_str::<ConnectorConfig>(r#"srtp_profiles = ["SRTP_BOGUS"]"#).unwrap_err();
+    let message = error.to_string();
+    assert!(message.contains("srtp_profiles"), "{}", message);
+    assert!(message.contains("SRTP_BOGUS"), "{}", message);
+
+    let error = toml::from_str::<ConnectorConfig>(r#"min_protocol = "dtls2""#).unwrap_err();
+    assert!(error.to_string().contains("min_protocol"), "{}", error);
+}
+
+#[test]
+fn unreadable_files_name_the_key() {
+    let config: ConnectorConfig =
+        toml::from_str(r#"root_certificates = ["test/server.der", "test/missing.der"]"#).unwrap();
+    let message = configuration_error(config.builder());
+    assert!(message.starts_with("root_certificates[1]: "), "{}", message);
+
+    let message = configuration_error(acceptor_config(r#"{ value = "wrong" }"#).builder());
+    assert!(message.starts_with("identity.pkcs12: "), "{}", message);
+}
+
+#[test]
+fn passwords_are_read_from_their_source() {
+    std::env::set_var("UDP_DTLS_TEST_PASSWORD", "mypass");
+    assert!(acceptor_config(r#"{ env = "UDP_DTLS_TEST_PASSWORD" }"#)
+        .builder()
+        .is_ok());
+
+    let message = configuration_error(
+        acceptor_config(r#"{ env = "UDP_DTLS_TEST_PASSWORD_MISSING" }"#).builder(),
+    );
+    assert!(message.starts_with("identity.password: "), "{}", message);
+
+    let config = acceptor_config(r#"{ value = "secret" }"#);
+    assert!(!format!("{:?}", config).contains("secret"));
+}
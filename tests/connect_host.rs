@@ -0,0 +1,98 @@
+mod common;
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, UdpSocket},
+    thread,
+    time::Duration,
+};
+use udp_dtls::{DtlsAcceptor, DtlsConnector, DtlsConnectorBuilder, ErrorKind, UdpChannel};
+
+fn connector() -> DtlsConnectorBuilder {
+    let mut builder = DtlsConnector::builder();
+    builder
+        .add_root_certificate(common::root_certificate())
+        .handshake_timeout(Some(Duration::from_millis(500)));
+    builder
+}
+
+/// Accepts one client on a new socket and echoes its first message, returning the port.
+fn echo_server() -> (u16, thread::JoinHandle<()>) {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let port = socket.local_addr().unwrap().port();
+
+    let server = thread::spawn(move || {
+        let acceptor = DtlsAcceptor::builder(common::identity()).build().unwrap();
+        let (_, remote_addr) = socket.peek_from(&mut [0; 1]).unwrap();
+        let channel = UdpChannel {
+            socket,
+            remote_addr,
+        };
+        let mut stream = match acceptor.accept(channel) {
+            Ok(stream) => stream,
+            Err(_) => return,
+        };
+        let mut buf = [0; 64];
+        let read = stream.read(&mut buf).unwrap();
+        stream.write_all(&buf[..read]).unwrap();
+    });
+    (port, server)
+}
+
+#[test]
+fn connects_to_resolved_host() {
+    let (port, server) = echo_server();
+    let connector = connector()
+        .danger_accept_invalid_hostnames(true)
+        .build()
+        .unwrap();
+
+    let mut stream = connector
+        .connect_host(&format!("localhost:{}", port))
+        .unwrap();
+    assert_eq!(
+        stream.get_ref().remote_addr,
+        SocketAddr::from(([127, 0, 0, 1], port))
+    );
+    stream.write_all(b"hello").unwrap();
+    let mut buf = [0; 64];
+    let read = stream.read(&mut buf).unwrap();
+    assert_eq!(&buf[..read], b"hello");
+    server.join().unwrap();
+}
+
+#[test]
+fn host_is_verified() {
+    let (port, server) = echo_server();
+    let connector = connector().build().unwrap();
+
+    let error = connector
+        .connect_host(&format!("localhost:{}", port))
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::HostnameMismatch);
+    server.join().unwrap();
+}
+
+#[test]
+fn silent_addresses_time_out() {
+    let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let port = silent.local_addr().unwrap().port();
+    let connector = connector().build().unwrap();
+
+    let error = connector
+        .connect_host(&format!("127.0.0.1:{}", port))
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::HandshakeTimeout);
+}
+
+#[test]
+fn invalid_addresses_are_rejected() {
+    let connector = connector().build().unwrap();
+
+    let error = connector.connect_host("localhost").unwrap_err();
+    assert_eq!(
+        error.kind(),
+        ErrorKind::Io(std::io::ErrorKind::InvalidInput)
+    );
+    assert!(connector.connect_host("localhost:port").is_err());
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use std::io;
+use udp_dtls::{DtlsAcceptor, DtlsConnector, DtlsStream, UdpChannel, MAX_DATAGRAM_SIZE};
+
+fn streams() -> (DtlsStream<UdpChannel>, DtlsStream<UdpChannel>) {
+    let acceptor = DtlsAcceptor::builder(common::identity()).build().unwrap();
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .build()
+        .unwrap();
+
+    let (server, client) = common::handshake(acceptor, connector);
+    (server.unwrap(), client.unwrap())
+}
+
+#[test]
+fn message_boundaries_are_preserved() {
+    let (mut server, mut client) = streams();
+
+    client.send_datagram(b"hello").unwrap();
+    client.send_datagram(b"world!").unwrap();
+
+    let mut buf = [0; MAX_DATAGRAM_SIZE];
+    let n = server.recv_datagram(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"hello");
+    let n = server.recv_datagram(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"world!");
+}
+
+#[test]
+fn largest_datagram_fits_one_record() {
+    let (mut server, mut client) = streams();
+
+    let message = vec![7; MAX_DATAGRAM_SIZE];
+    client.send_datagram(&message).unwrap();
+
+    let mut buf = vec![0; MAX_DATAGRAM_SIZE];
+    let n = server.recv_datagram(&mut buf).unwrap();
+    assert_eq!(buf[..n], message[..]);
+}
+
+#[test]
+fn oversized_and_empty_datagrams_are_rejected() {
+    let (_server, mut client) = streams();
+
+    let err = client
+        .send_datagram(&vec![0; MAX_DATAGRAM_SIZE + 1])
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+    let err = client.send_datagram(&[]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn truncation_is_reported_and_remainder_discarded() {
+    let (mut server, mut client) = streams();
+
+    client.send_datagram(&[1; 3000]).unwrap();
+    client.send_datagram(b"next").unwrap();
+
+    let mut small = [0; 100];
+    let err = server.recv_datagram(&mut small).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(small, [1; 100]);
+
+    let mut buf = [0; MAX_DATAGRAM_SIZE];
+    let n = server.recv_datagram(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"next");
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use udp_dtls::{DtlsAcceptor, DtlsConnector, ErrorKind, Protocol};
+
+fn acceptor() -> DtlsAcceptor {
+    DtlsAcceptor::builder(common::identity()).build().unwrap()
+}
+
+#[test]
+fn untrusted_certificate() {
+    let connector = DtlsConnector::builder().build().unwrap();
+
+    let (server, client) = common::handshake(acceptor(), connector);
+
+    match client.unwrap_err().kind() {
+        ErrorKind::CertificateVerification(_) => {}
+        kind => panic!("unexpected kind {:?}", kind),
+    }
+    // unknown_ca
+    assert_eq!(server.unwrap_err().kind(), ErrorKind::AlertReceived(48));
+}
+
+#[test]
+fn hostname_mismatch() {
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .build()
+        .unwrap();
+
+    let (_server, client) = common::handshake_to(acceptor(), connector, "example.com");
+
+    assert_eq!(client.unwrap_err().kind(), ErrorKind::HostnameMismatch);
+}
+
+#[test]
+fn no_shared_cipher() {
+    let acceptor = DtlsAcceptor::builder(common::identity())
+        .add_cipher("ECDHE-ECDSA-AES256-GCM-SHA384")
+        .build()
+        .unwrap();
+    let connector = DtlsConnector::builder()
+        .add_cipher("ECDHE-ECDSA-AES128-GCM-SHA256")
+        .add_root_certificate(common::root_certificate())
+        .build()
+        .unwrap();
+
+    let (server, _client) = common::handshake(acceptor, connector);
+
+    assert_eq!(server.unwrap_err().kind(), ErrorKind::NoSharedCipher);
+}
+
+#[test]
+fn protocol_version_mismatch() {
+    let acceptor = DtlsAcceptor::builder(common::identity())
+        .min_protocol_version(Some(Protocol::Dtlsv12))
+        .build()
+        .unwrap();
+    let connector = DtlsConnector::builder()
+        .max_protocol_version(Some(Protocol::Dtlsv10))
+        .add_root_certificate(common::root_certificate())
+        .build()
+        .unwrap();
+
+    let (server, _client) = common::handshake(acceptor, connector);
+
+    assert_eq!(server.unwrap_err().kind(), ErrorKind::ProtocolVersion);
+}
+
+#[test]
+fn configuration_error() {
+    let error = match DtlsConnector::builder().add_cipher("NOT-A-CIPHER").build() {
+        Ok(_) => panic!("unknown cipher was accepted"),
+        Err(e) => e,
+    };
+
+    assert_eq!(error.kind(), ErrorKind::Configuration);
+    assert!(error.error_stack().is_some());
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use std::time::{Duration, Instant};
+use udp_dtls::{DtlsAcceptor, DtlsConnector, ErrorKind, HandshakeError, ReadTimeout};
+
+#[test]
+fn connect_with_deadline_times_out_when_peer_is_silent() {
+    let connector = DtlsConnector::builder().build().unwrap();
+    // The server end is kept alive but never answers.
+    let (_server, client) = common::channel_pair();
+
+    let start = Instant::now();
+    let deadline = start + Duration::from_millis(300);
+    match connector.connect_with_deadline("foobar.com", client, deadline) {
+        Err(HandshakeError::Failure(e)) => assert_eq!(e.kind(), ErrorKind::HandshakeTimeout),
+        Err(HandshakeError::WouldBlock(_)) => panic!("handshake on a blocking socket would block"),
+        Ok(_) => panic!("handshake succeeded without a peer"),
+    }
+    assert!(start.elapsed() < Duration::from_secs(2));
+}
+
+#[test]
+fn accept_with_deadline_times_out_when_peer_is_silent() {
+    let acceptor = DtlsAcceptor::builder(common::identity()).build().unwrap();
+    let (server, _client) = common::channel_pair();
+
+    let deadline = Instant::now() + Duration::from_millis(300);
+    match acceptor.accept_with_deadline(server, deadline) {
+        Err(HandshakeError::Failure(e)) => assert_eq!(e.kind(), ErrorKind::HandshakeTimeout),
+        Err(HandshakeError::WouldBlock(_)) => panic!("handshake on a blocking socket would block"),
+        Ok(_) => panic!("handshake succeeded without a peer"),
+    }
+    assert!(Instant::now() < deadline + Duration::from_secs(2));
+}
+
+#[test]
+fn handshake_within_timeout_restores_read_timeout() {
+    let acceptor = DtlsAcceptor::builder(common::identity())
+        .handshake_timeout(Some(Duration::from_secs(5)))
+        .build()
+        .unwrap();
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .handshake_timeout(Some(Duration::from_secs(5)))
+        .build()
+        .unwrap();
+
+    let (server, client) = common::handshake(acceptor, connector);
+
+    assert_eq!(server.unwrap().get_ref().read_timeout().unwrap(), None);
+    assert_eq!(client.unwrap().get_ref().read_timeout().unwrap(), None);
+}
+
+#[test]
+fn nonblocking_handshakes_are_interrupted_and_time_out() {
+    let connector = DtlsConnector::builder()
+        .handshake_timeout(Some(Duration::from_millis(300)))
+        .build()
+        .unwrap();
+    let (_server, client) = common::channel_pair();
+    client.socket.set_nonblocking(true).unwrap();
+
+    let start = Instant::now();
+    let deadline = start + Duration::from_millis(300);
+    let mid = match connector.connect_with_deadline("foobar.com", client, deadline) {
+        Err(HandshakeError::WouldBlock(mid)) => mid,
+        _ => panic!("handshake on a nonblocking socket should be interrupted"),
+    };
+    assert!(start.elapsed() < Duration::from_millis(200));
+    assert_eq!(mid.deadline(), Some(deadline));
+
+    let mid = match mid.handshake() {
+        Err(HandshakeError::WouldBlock(mid)) => mid,
+        _ => panic!("handshake without a peer should be interrupted"),
+    };
+    std::thread::sleep(Duration::from_millis(300));
+    match mid.handshake() {
+        Err(HandshakeError::Failure(e)) => assert_eq!(e.kind(), ErrorKind::HandshakeTimeout),
+        _ => panic!("resumed handshake should time out"),
+    }
+}
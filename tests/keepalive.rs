@@ -0,0 +1,189 @@
+mod common;
+
+use std::{
+    io::{self, Read, Write},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+use udp_dtls::{
+    DtlsAcceptor, DtlsConnector, DtlsSession, Error, ErrorKind, EvictionReason, ReadTimeout,
+    SessionTable, MAX_DATAGRAM_SIZE,
+};
+
+const INTERVAL: Duration = Duration::from_millis(50);
+
+fn acceptor() -> DtlsAcceptor {
+    DtlsAcceptor::builder(common::identity())
+        .keepalive_interval(Some(INTERVAL))
+        .build()
+        .unwrap()
+}
+
+fn connector(max_missed: u32) -> DtlsConnector {
+    DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .keepalive_interval(Some(INTERVAL))
+        .keepalive_max_missed(max_missed)
+        .build()
+        .unwrap()
+}
+
+fn pump(from: &mut DtlsSession, to: &mut DtlsSession) -> usize {
+    let mut moved = 0;
+    while let Some(datagram) = from.poll_datagram() {
+        to.handle_datagram(&datagram).unwrap();
+        moved += 1;
+    }
+    moved
+}
+
+fn handshake(client: &mut DtlsSession, server: &mut DtlsSession) {
+    while client.is_handshaking() || server.is_handshaking() {
+        let moved = pump(client, server) + pump(server, client);
+        assert!(moved > 0, "handshake stalled");
+    }
+}
+
+/// Waits until the keepalive probe of `session` is due and handles it.
+fn expire(session: &mut DtlsSession) -> udp_dtls::Result<bool> {
+    thread::sleep(session.timeout().unwrap());
+    session.handle_timeout()
+}
+
+#[test]
+fn idle_peers_are_probed_and_answer() {
+    let mut client = connector(3).connect_session("foobar.com").unwrap();
+    let mut server = acceptor().accept_session().unwrap();
+    handshake(&mut client, &mut server);
+    assert!(client.timeout().unwrap() <= INTERVAL);
+
+    assert!(expire(&mut client).unwrap());
+    assert_eq!(pump(&mut client, &mut server), 1);
+
+    // the probe is answered and not returned to the application
+    let mut buf = [0; MAX_DATAGRAM_SIZE];
+    let error = server.read(&mut buf).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::WouldBlock);
+    assert_eq!(pump(&mut server, &mut client), 1);
+    let error = client.read(&mut buf).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::WouldBlock);
+
+    // application data still flows around the probes
+    for _ in 0..3 {
+        assert!(expire(&mut client).unwrap());
+        pump(&mut client, &mut server);
+        server.read(&mut buf).unwrap_err();
+        pump(&mut server, &mut client);
+        client.read(&mut buf).unwrap_err();
+    }
+    client.write_all(b"hello").unwrap();
+    pump(&mut client, &mut server);
+    let read = server.read(&mut buf).unwrap();
+    assert_eq!(&buf[..read], b"hello");
+}
+
+#[test]
+fn peers_without_keepalive_are_not_probed() {
+    let mut client = connector(1).connect_session("foobar.com").unwrap();
+    let mut server = DtlsAcceptor::builder(common::identity())
+        .build()
+        .unwrap()
+        .accept_session()
+        .unwrap();
+    handshake(&mut client, &mut server);
+
+    assert_eq!(client.timeout(), None);
+    thread::sleep(INTERVAL * 3);
+    for _ in 0..3 {
+        assert!(!client.handle_timeout().unwrap());
+    }
+    assert!(client.poll_datagram().is_none());
+}
+
+#[test]
+fn dead_peers_are_reported_after_missed_probes() {
+    let mut client = connector(2).connect_session("foobar.com").unwrap();
+    let mut server = acceptor().accept_session().unwrap();
+    handshake(&mut client, &mut server);
+
+    // the server is gone, the probes are lost
+    assert!(expire(&mut client).unwrap());
+    assert!(expire(&mut client).unwrap());
+    while client.poll_datagram().is_some() {}
+
+    let error = expire(&mut client).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::KeepaliveTimeout);
+    assert!(client.poll_datagram().is_none());
+
+    let mut buf = [0; MAX_DATAGRAM_SIZE];
+    let error = client.read(&mut buf).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+    let error = error.into_inner().unwrap().downcast::<Error>().unwrap();
+    assert_eq!(error.kind(), ErrorKind::KeepaliveTimeout);
+}
+
+#[test]
+fn blocking_streams_keep_the_session_alive() {
+    let (server, client) = common::handshake(acceptor(), connector(2));
+    let mut server = server.unwrap();
+    let mut client = client.unwrap();
+
+    let server = thread::spawn(move || {
+        server.get_mut().set_read_timeout(Some(INTERVAL)).unwrap();
+        let mut buf = [0; MAX_DATAGRAM_SIZE];
+        loop {
+            match server.read(&mut buf) {
+                Ok(read) => return buf[..read].to_vec(),
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    server.handle_timeout().unwrap();
+                }
+                Err(e) => panic!("{}", e),
+            }
+        }
+    });
+
+    // idle for longer than the client tolerates unanswered probes
+    client.get_mut().set_read_timeout(Some(INTERVAL)).unwrap();
+    let mut buf = [0; MAX_DATAGRAM_SIZE];
+    for _ in 0..8 {
+        let error = client.read(&mut buf).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::WouldBlock);
+        client.handle_timeout().unwrap();
+    }
+
+    client.write_all(b"still there").unwrap();
+    assert_eq!(server.join().unwrap(), b"still there");
+}
+
+#[test]
+fn unresponsive_sessions_are_evicted_from_tables() {
+    let evicted = Arc::new(Mutex::new(Vec::new()));
+    let record = evicted.clone();
+    let mut table = SessionTable::builder(acceptor())
+        .on_session_evicted(move |peer, _, reason| record.lock().unwrap().push((peer, reason)))
+        .build();
+
+    let peer = SocketAddr::from(([127, 0, 0, 1], 1));
+    let mut client = connector(3).connect_session("foobar.com").unwrap();
+    while client.is_handshaking() {
+        while let Some(datagram) = client.poll_datagram() {
+            table.handle_datagram(peer, &datagram).unwrap();
+        }
+        let session = table.get_mut(&peer).unwrap();
+        while let Some(datagram) = session.poll_datagram() {
+            client.handle_datagram(&datagram).unwrap();
+        }
+    }
+
+    // the client is gone, the server's probes are lost
+    while !table.is_empty() {
+        thread::sleep(table.next_timeout().unwrap());
+        table.handle_timeouts();
+    }
+    assert_eq!(
+        *evicted.lock().unwrap(),
+        vec![(peer, EvictionReason::Unresponsive)]
+    );
+}
@@ -0,0 +1,86 @@
+mod common;
+
+#[test]
+#[cfg(not(any(debug_assertions, feature = "keylog")))]
+fn release_build_refuses_key_log() {
+    use udp_dtls::{DtlsConnector, ErrorKind, KeyLog};
+
+    let err = DtlsConnector::builder()
+        .key_log(Some(KeyLog::callback(|_| {})))
+        .build()
+        .err()
+        .expect("key logging should be refused in release builds");
+    assert_eq!(err.kind(), ErrorKind::Configuration);
+}
+
+#[cfg(any(debug_assertions, feature = "keylog"))]
+mod enabled {
+    use super::common;
+    use std::{
+        env, fs,
+        sync::{Arc, Mutex},
+    };
+    use udp_dtls::{DtlsAcceptor, DtlsConnector, ErrorKind, KeyLog};
+
+    #[test]
+    fn callback_receives_nss_key_log_lines() {
+        let client_lines = Arc::new(Mutex::new(Vec::new()));
+        let server_lines = Arc::new(Mutex::new(Vec::new()));
+
+        let lines = client_lines.clone();
+        let connector = DtlsConnector::builder()
+            .add_root_certificate(common::root_certificate())
+            .key_log(Some(KeyLog::callback(move |line| {
+                lines.lock().unwrap().push(line.to_string())
+            })))
+            .build()
+            .unwrap();
+        let lines = server_lines.clone();
+        let acceptor = DtlsAcceptor::builder(common::identity())
+            .key_log(Some(KeyLog::callback(move |line| {
+                lines.lock().unwrap().push(line.to_string())
+            })))
+            .build()
+            .unwrap();
+
+        let (server, client) = common::handshake(acceptor, connector);
+        server.unwrap();
+        client.unwrap();
+
+        let client_lines = client_lines.lock().unwrap();
+        let server_lines = server_lines.lock().unwrap();
+        assert_eq!(client_lines.len(), 1);
+        assert!(client_lines[0].starts_with("CLIENT_RANDOM "));
+        assert_eq!(*client_lines, *server_lines);
+    }
+
+    #[test]
+    fn ssl_key_log_file() {
+        // The only test in this binary that touches the environment.
+        env::remove_var("SSLKEYLOGFILE");
+        let err = DtlsConnector::builder()
+            .key_log(Some(KeyLog::SslKeyLogFile))
+            .build()
+            .err()
+            .expect("build without SSLKEYLOGFILE should fail");
+        assert_eq!(err.kind(), ErrorKind::Configuration);
+
+        let path = env::temp_dir().join(format!("udp-dtls-keylog-{}.txt", std::process::id()));
+        env::set_var("SSLKEYLOGFILE", &path);
+        let connector = DtlsConnector::builder()
+            .add_root_certificate(common::root_certificate())
+            .key_log(Some(KeyLog::SslKeyLogFile))
+            .build()
+            .unwrap();
+        let acceptor = DtlsAcceptor::builder(common::identity()).build().unwrap();
+
+        let (server, client) = common::handshake(acceptor, connector);
+        server.unwrap();
+        client.unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(contents.starts_with("CLIENT_RANDOM "));
+        assert!(contents.ends_with('\n'));
+    }
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use udp_dtls::{DtlsAcceptor, DtlsConnector, ErrorKind, HandshakeError, InMemoryMetrics};
+
+#[test]
+fn handshake_and_traffic_are_counted() {
+    let client_metrics = Arc::new(InMemoryMetrics::new());
+    let server_metrics = Arc::new(InMemoryMetrics::new());
+
+    let acceptor = DtlsAcceptor::builder(common::identity())
+        .metrics(Some(server_metrics.clone()))
+        .build()
+        .unwrap();
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .metrics(Some(client_metrics.clone()))
+        .build()
+        .unwrap();
+
+    let (server, client) = common::handshake(acceptor, connector);
+    let (mut server, mut client) = (server.unwrap(), client.unwrap());
+
+    for metrics in &[&client_metrics, &server_metrics] {
+        assert_eq!(metrics.handshakes_started(), 1);
+        assert_eq!(metrics.handshakes_completed(), 1);
+        assert_eq!(metrics.handshake_durations().len(), 1);
+        assert!(metrics.failures().is_empty());
+    }
+
+    client.send_datagram(b"hello").unwrap();
+    let mut buf = [0; 16];
+    assert_eq!(server.recv_datagram(&mut buf).unwrap(), 5);
+
+    assert_eq!(client_metrics.bytes_sent(), 5);
+    assert_eq!(server_metrics.bytes_received(), 5);
+    assert_eq!(
+        server_metrics.session_bytes(server.session_id().unwrap()),
+        (5, 0)
+    );
+    assert_eq!(
+        client_metrics.session_bytes(client.session_id().unwrap()),
+        (0, 5)
+    );
+    assert_ne!(server.session_id(), client.session_id());
+}
+
+#[test]
+fn failures_are_classified() {
+    let metrics = Arc::new(InMemoryMetrics::new());
+
+    let acceptor = DtlsAcceptor::builder(common::identity()).build().unwrap();
+    let connector = DtlsConnector::builder()
+        .metrics(Some(metrics.clone()))
+        .build()
+        .unwrap();
+
+    let (_server, client) = common::handshake(acceptor, connector);
+    assert!(client.is_err());
+
+    assert_eq!(metrics.handshakes_started(), 1);
+    assert_eq!(metrics.handshakes_completed(), 0);
+    match metrics.failures()[..] {
+        [ErrorKind::CertificateVerification(_)] => {}
+        ref failures => panic!("unexpected failures {:?}", failures),
+    }
+}
+
+#[test]
+fn retransmissions_and_timeouts_are_counted() {
+    let metrics = Arc::new(InMemoryMetrics::new());
+    let connector = DtlsConnector::builder()
+        .metrics(Some(metrics.clone()))
+        .build()
+        .unwrap();
+    let (_silent, client) = common::channel_pair();
+
+    let deadline = Instant::now() + Duration::from_millis(1500);
+    match connector.connect_with_deadline("foobar.com", client, deadline) {
+        Err(HandshakeError::Failure(e)) => assert_eq!(e.kind(), ErrorKind::HandshakeTimeout),
+        _ => panic!("handshake without a peer should time out"),
+    }
+
+    assert_eq!(metrics.retransmissions(), 1);
+    assert_eq!(metrics.failures(), vec![ErrorKind::HandshakeTimeout]);
+}
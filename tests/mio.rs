@@ -0,0 +1,135 @@
+#![cfg(feature = "mio")]
+
+mod common;
+
+use mio::{net::UdpSocket, Events, Interest, Poll, Token};
+use std::{fmt, io, thread};
+use udp_dtls::{
+    DtlsAcceptor, DtlsConnector, DtlsStream, HandshakeError, MioUdpChannel, MAX_DATAGRAM_SIZE,
+};
+
+const STREAM: Token = Token(0);
+
+fn channel_pair() -> (MioUdpChannel, MioUdpChannel) {
+    let server = UdpSocket::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+    let client = UdpSocket::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+
+    let server_addr = server.local_addr().unwrap();
+    let client_addr = client.local_addr().unwrap();
+
+    (
+        MioUdpChannel {
+            socket: server,
+            remote_addr: client_addr,
+        },
+        MioUdpChannel {
+            socket: client,
+            remote_addr: server_addr,
+        },
+    )
+}
+
+/// Drives the handshake with `poll`, sleeping until readiness or the retransmission timer.
+///
+/// The returned stream is registered for readable events.
+fn drive<S>(
+    poll: &mut Poll,
+    mut result: Result<DtlsStream<S>, HandshakeError<S>>,
+) -> DtlsStream<S>
+where
+    S: io::Read + io::Write + mio::event::Source + fmt::Debug,
+{
+    let mut events = Events::with_capacity(4);
+    let mut registered = false;
+
+    loop {
+        match result {
+            Ok(mut stream) => {
+                if !registered {
+                    poll.registry()
+                        .register(&mut stream, STREAM, Interest::READABLE)
+                        .unwrap();
+                }
+                return stream;
+            }
+            Err(HandshakeError::WouldBlock(mut mid)) => {
+                if !registered {
+                    poll.registry()
+                        .register(&mut mid, STREAM, Interest::READABLE)
+                        .unwrap();
+                    registered = true;
+                }
+
+                poll.poll(&mut events, mid.timeout()).unwrap();
+                if events.is_empty() {
+                    mid.handle_timeout().unwrap();
+                }
+                result = mid.handshake();
+            }
+            Err(HandshakeError::Failure(e)) => panic!("handshake failed: {}", e),
+        }
+    }
+}
+
+#[test]
+fn handshake_and_datagrams_in_poll_loop() {
+    let acceptor = DtlsAcceptor::builder(common::identity()).build().unwrap();
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .build()
+        .unwrap();
+    let (server_channel, client_channel) = channel_pair();
+
+    let server = thread::spawn(move || {
+        let mut poll = Poll::new().unwrap();
+        let mut stream = drive(&mut poll, acceptor.accept(server_channel));
+
+        let mut events = Events::with_capacity(4);
+        let mut buf = [0; MAX_DATAGRAM_SIZE];
+        let mut received = Vec::new();
+        while received.len() < 3 {
+            poll.poll(&mut events, None).unwrap();
+            loop {
+                match stream.recv_datagram(&mut buf) {
+                    Ok(n) => received.push(buf[..n].to_vec()),
+                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(e) => panic!("read failed: {}", e),
+                }
+            }
+        }
+        received
+    });
+
+    let mut poll = Poll::new().unwrap();
+    let mut client = drive(&mut poll, connector.connect("foobar.com", client_channel));
+    for message in &[&b"one"[..], b"two", b"three"] {
+        client.send_datagram(message).unwrap();
+    }
+
+    assert_eq!(
+        server.join().unwrap(),
+        vec![b"one".to_vec(), b"two".to_vec(), b"three".to_vec()]
+    );
+}
+
+#[test]
+fn retransmission_timer() {
+    let connector = DtlsConnector::builder().build().unwrap();
+    let (silent, client_channel) = channel_pair();
+
+    let mut mid = match connector.connect("foobar.com", client_channel) {
+        Err(HandshakeError::WouldBlock(mid)) => mid,
+        _ => panic!("handshake on a nonblocking socket without a peer should block"),
+    };
+
+    let timeout = mid.timeout().expect("retransmission timer should be running");
+    assert!(!mid.handle_timeout().unwrap());
+
+    thread::sleep(timeout);
+    assert!(mid.handle_timeout().unwrap());
+
+    // The initial flight and its retransmission.
+    let mut buf = [0; MAX_DATAGRAM_SIZE];
+    assert!(silent.socket.recv(&mut buf).is_ok());
+    assert!(silent.socket.recv(&mut buf).is_ok());
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use udp_dtls::{
+    DtlsAcceptor, DtlsConnector, DtlsStream, Error, ErrorKind, OcspPolicy, OcspStaple, OcspStatus,
+    UdpChannel,
+};
+
+const GOOD: &[u8] = include_bytes!("../test/leaf-ocsp-good.der");
+const REVOKED: &[u8] = include_bytes!("../test/leaf-ocsp-revoked.der");
+
+fn handshake(
+    staple: Option<&OcspStaple>,
+    policy: Option<OcspPolicy>,
+) -> (
+    Result<DtlsStream<UdpChannel>, Error>,
+    Result<DtlsStream<UdpChannel>, Error>,
+) {
+    let acceptor = DtlsAcceptor::builder(common::leaf_identity())
+        .ocsp_staple(staple.cloned())
+        .build()
+        .unwrap();
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::test_ca())
+        .ocsp_stapling(policy)
+        .build()
+        .unwrap();
+
+    common::handshake(acceptor, connector)
+}
+
+fn client_status(staple: Option<&OcspStaple>, policy: OcspPolicy) -> Option<OcspStatus> {
+    handshake(staple, Some(policy)).1.unwrap().ocsp_status()
+}
+
+#[test]
+fn good_responses_are_accepted() {
+    let staple = OcspStaple::from_der(GOOD).unwrap();
+    for policy in [OcspPolicy::Soft, OcspPolicy::Hard] {
+        assert_eq!(client_status(Some(&staple), policy), Some(OcspStatus::Good));
+    }
+}
+
+#[test]
+fn revoked_certificates_are_rejected() {
+    let staple = OcspStaple::from_der(REVOKED).unwrap();
+    for policy in [OcspPolicy::Soft, OcspPolicy::Hard] {
+        let error = handshake(Some(&staple), Some(policy)).1.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::OcspStatus);
+    }
+}
+
+#[test]
+fn missing_responses_depend_on_the_policy() {
+    let staple = OcspStaple::new();
+    assert_eq!(
+        client_status(Some(&staple), OcspPolicy::Soft),
+        Some(OcspStatus::NotStapled)
+    );
+    assert_eq!(
+        client_status(None, OcspPolicy::Soft),
+        Some(OcspStatus::NotStapled)
+    );
+
+    let error = handshake(Some(&staple), Some(OcspPolicy::Hard))
+        .1
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::OcspStatus);
+}
+
+#[test]
+fn responses_not_covering_the_certificate_are_not_stapled() {
+    let staple = OcspStaple::from_der(GOOD).unwrap();
+    let acceptor = DtlsAcceptor::builder(common::identity())
+        .ocsp_staple(Some(staple))
+        .build()
+        .unwrap();
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .ocsp_stapling(Some(OcspPolicy::Soft))
+        .build()
+        .unwrap();
+
+    let (server, client) = common::handshake(acceptor, connector);
+    server.unwrap();
+    assert_eq!(client.unwrap().ocsp_status(), Some(OcspStatus::NotStapled));
+}
+
+#[test]
+fn staples_are_refreshed() {
+    let staple = OcspStaple::from_der(REVOKED).unwrap();
+    let acceptor = DtlsAcceptor::builder(common::leaf_identity())
+        .ocsp_staple(Some(staple.clone()))
+        .build()
+        .unwrap();
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::test_ca())
+        .ocsp_stapling(Some(OcspPolicy::Hard))
+        .build()
+        .unwrap();
+
+    assert!(common::handshake(acceptor.clone(), connector.clone())
+        .1
+        .is_err());
+
+    staple.set(GOOD).unwrap();
+    let (server, client) = common::handshake(acceptor, connector);
+    assert_eq!(server.unwrap().ocsp_status(), None);
+    assert_eq!(client.unwrap().ocsp_status(), Some(OcspStatus::Good));
+}
+
+#[test]
+fn stapling_is_only_requested_when_enabled() {
+    let staple = OcspStaple::from_der(GOOD).unwrap();
+    let (server, client) = handshake(Some(&staple), None);
+    server.unwrap();
+    assert_eq!(client.unwrap().ocsp_status(), None);
+}
+
+#[test]
+fn malformed_responses_are_refused() {
+    let staple = OcspStaple::from_der(GOOD).unwrap();
+    let error = staple.set(b"not an OCSP response").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Configuration);
+    assert!(staple.is_set());
+
+    staple.clear();
+    assert!(!staple.is_set());
+}
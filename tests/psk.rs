@@ -0,0 +1,125 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+use udp_dtls::{
+    CipherPreset, ConnectorIdentity, DtlsAcceptor, DtlsConnector, DtlsConnectorBuilder, Error,
+    PskIdentity,
+};
+
+fn site(name: &str) -> PskIdentity {
+    PskIdentity::new(name.as_bytes(), format!("{} key", name).as_bytes())
+}
+
+fn acceptor(hint: &[u8], identity: PskIdentity) -> DtlsAcceptor {
+    DtlsAcceptor::builder(common::identity())
+        .cipher_preset(CipherPreset::PskOnly)
+        .psk_identity_hint(hint)
+        .add_psk_identity(identity)
+        .build()
+        .unwrap()
+}
+
+fn connector() -> DtlsConnectorBuilder {
+    let mut builder = DtlsConnector::builder();
+    builder.cipher_preset(CipherPreset::PskOnly);
+    builder
+}
+
+fn configuration_error(result: udp_dtls::Result<impl Sized>) -> String {
+    match result {
+        Err(Error::Configuration(message)) => message,
+        Err(e) => panic!("expected a configuration error, got {:?}", e),
+        Ok(_) => panic!("expected a configuration error"),
+    }
+}
+
+#[test]
+fn identity_is_chosen_by_hint() {
+    let connector = connector()
+        .add_psk_identity_for_hint(b"site-a", site("a"))
+        .add_psk_identity_for_hint(b"site-b", site("b"))
+        .identity(ConnectorIdentity::Psk(site("default")))
+        .build()
+        .unwrap();
+
+    for (hint, identity) in [("site-a", "a"), ("site-b", "b"), ("other", "default")] {
+        let acceptor = acceptor(hint.as_bytes(), site(identity));
+        let (server, client) = common::handshake(acceptor, connector.clone());
+        assert!(server.is_ok() && client.is_ok(), "hint {}", hint);
+    }
+}
+
+#[test]
+fn callback_chooses_before_the_table() {
+    let hints = Arc::new(Mutex::new(Vec::new()));
+    let record = hints.clone();
+    let connector = connector()
+        .psk_identity_callback(move |hint| {
+            record.lock().unwrap().push(hint.map(|hint| hint.to_vec()));
+            match hint {
+                Some(b"site-a") => Some(site("a")),
+                _ => None,
+            }
+        })
+        .add_psk_identity_for_hint(b"site-a", site("wrong"))
+        .add_psk_identity_for_hint(b"site-b", site("b"))
+        .build()
+        .unwrap();
+
+    let (server, client) = common::handshake(acceptor(b"site-a", site("a")), connector.clone());
+    assert!(server.is_ok() && client.is_ok());
+    let (server, client) = common::handshake(acceptor(b"site-b", site("b")), connector);
+    assert!(server.is_ok() && client.is_ok());
+
+    assert_eq!(
+        *hints.lock().unwrap(),
+        vec![Some(b"site-a".to_vec()), Some(b"site-b".to_vec())]
+    );
+}
+
+#[test]
+fn unknown_hints_fail_the_handshake() {
+    let connector = connector()
+        .add_psk_identity_for_hint(b"site-a", site("a"))
+        .build()
+        .unwrap();
+
+    let (server, client) = common::handshake(acceptor(b"site-b", site("b")), connector);
+    assert!(server.is_err());
+    assert!(client.is_err());
+}
+
+#[test]
+fn unusable_psk_material_fails_to_build() {
+    let long_key = PskIdentity::new(b"client", &[7; 257]);
+    let error = configuration_error(
+        connector()
+            .identity(ConnectorIdentity::Psk(long_key.clone()))
+            .build(),
+    );
+    assert!(error.contains("257 bytes"));
+    configuration_error(
+        DtlsAcceptor::builder(common::identity())
+            .add_psk_identity(long_key)
+            .build(),
+    );
+
+    let long_identity = PskIdentity::new(&[b'a'; 129], b"key");
+    let error = configuration_error(
+        connector()
+            .add_psk_identity_for_hint(b"hint", long_identity)
+            .build(),
+    );
+    assert!(error.contains("129 bytes"));
+    configuration_error(
+        DtlsAcceptor::builder(common::identity())
+            .psk_identity_hint(b"nul\0hint")
+            .build(),
+    );
+
+    let longest = PskIdentity::new(&[b'a'; 128], &[7; 256]);
+    assert!(connector()
+        .identity(ConnectorIdentity::Psk(longest))
+        .build()
+        .is_ok());
+}
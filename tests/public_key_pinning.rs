@@ -0,0 +1,80 @@
+mod common;
+
+use openssl::x509::X509VerifyResult;
+use udp_dtls::{Certificate, ConnectorIdentity, DtlsAcceptor, DtlsConnector, ErrorKind, PublicKey};
+
+fn server_key() -> PublicKey {
+    common::root_certificate().public_key().unwrap()
+}
+
+fn other_key() -> PublicKey {
+    Certificate::from_pem(include_bytes!("../test/root-ca.pem"))
+        .unwrap()
+        .public_key()
+        .unwrap()
+}
+
+#[test]
+fn pinned_servers_are_accepted_without_roots() {
+    let acceptor = DtlsAcceptor::builder(common::identity()).build().unwrap();
+    let connector = DtlsConnector::builder()
+        .use_system_roots(false)
+        .pin_certificate_key(other_key())
+        .pin_certificate_key(server_key())
+        .build()
+        .unwrap();
+
+    let (_, client) = common::handshake_to(acceptor, connector, "not-foobar.com");
+    let client = client.unwrap();
+    assert_eq!(client.peer_public_key().unwrap(), Some(server_key()));
+}
+
+#[test]
+fn unpinned_servers_are_rejected() {
+    let acceptor = DtlsAcceptor::builder(common::identity()).build().unwrap();
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .pin_certificate_key(other_key())
+        .build()
+        .unwrap();
+
+    let (_, client) = common::handshake(acceptor, connector);
+    assert_eq!(
+        client.unwrap_err().kind(),
+        ErrorKind::CertificateVerification(X509VerifyResult::APPLICATION_VERIFICATION)
+    );
+}
+
+#[test]
+fn acceptors_authenticate_clients_by_pinned_keys() {
+    let acceptor = DtlsAcceptor::builder(common::identity())
+        .pin_certificate_key(server_key())
+        .build()
+        .unwrap();
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .identity(ConnectorIdentity::Certificate(common::identity()))
+        .build()
+        .unwrap();
+
+    let (server, client) = common::handshake(acceptor.clone(), connector);
+    client.unwrap();
+    assert_eq!(
+        server.unwrap().peer_public_key().unwrap(),
+        Some(server_key())
+    );
+
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .build()
+        .unwrap();
+    let (server, _) = common::handshake(acceptor, connector);
+    assert!(server.is_err());
+}
+
+#[test]
+fn keys_round_trip() {
+    let der = server_key().to_der().unwrap();
+    assert_eq!(PublicKey::from_der(&der).unwrap(), server_key());
+    assert_ne!(other_key(), server_key());
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use std::{io, net::UdpSocket};
+use udp_dtls::{
+    DtlsAcceptor, DtlsConnector, DtlsStream, InvalidRecordPolicy, UdpChannel, MAX_DATAGRAM_SIZE,
+};
+
+/// Streams whose client sends its records to a capture socket, from which the tests forward,
+/// duplicate or tamper with them before they reach the server.
+struct Intercepted {
+    server: DtlsStream<UdpChannel>,
+    client: DtlsStream<UdpChannel>,
+    capture: UdpSocket,
+}
+
+impl Intercepted {
+    fn new(policy: InvalidRecordPolicy) -> Intercepted {
+        let acceptor = DtlsAcceptor::builder(common::identity())
+            .invalid_record_policy(policy)
+            .build()
+            .unwrap();
+        let connector = DtlsConnector::builder()
+            .add_root_certificate(common::root_certificate())
+            .build()
+            .unwrap();
+
+        let (server, client) = common::handshake(acceptor, connector);
+        let mut client = client.unwrap();
+        let capture = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.get_mut().remote_addr = capture.local_addr().unwrap();
+
+        Intercepted {
+            server: server.unwrap(),
+            client,
+            capture,
+        }
+    }
+
+    /// Sends `message` from the client and returns the captured datagram.
+    fn capture(&mut self, message: &[u8]) -> Vec<u8> {
+        self.client.send_datagram(message).unwrap();
+        let mut buf = [0; MAX_DATAGRAM_SIZE];
+        let len = self.capture.recv(&mut buf).unwrap();
+        buf[..len].to_vec()
+    }
+
+    fn forward(&self, datagram: &[u8]) {
+        let server_addr = self.server.get_ref().socket.local_addr().unwrap();
+        self.capture.send_to(datagram, server_addr).unwrap();
+    }
+
+    fn recv(&mut self) -> io::Result<Vec<u8>> {
+        let mut buf = [0; MAX_DATAGRAM_SIZE];
+        let len = self.server.recv_datagram(&mut buf)?;
+        Ok(buf[..len].to_vec())
+    }
+}
+
+#[test]
+fn valid_records_are_not_counted() {
+    let mut streams = Intercepted::new(InvalidRecordPolicy::Drop);
+
+    for message in [&b"one"[..], b"two", b"three"] {
+        let datagram = streams.capture(message);
+        streams.forward(&datagram);
+        assert_eq!(streams.recv().unwrap(), message);
+    }
+
+    assert_eq!(streams.server.record_stats().dropped(), 0);
+}
+
+#[test]
+fn replayed_records_are_counted() {
+    let mut streams = Intercepted::new(InvalidRecordPolicy::Drop);
+
+    let first = streams.capture(b"first");
+    let second = streams.capture(b"second");
+    streams.forward(&first);
+    streams.forward(&first);
+    streams.forward(&second);
+
+    assert_eq!(streams.recv().unwrap(), b"first");
+    assert_eq!(streams.recv().unwrap(), b"second");
+
+    let stats = streams.server.record_stats();
+    assert_eq!(stats.replayed, 1);
+    assert_eq!(stats.bad_mac, 0);
+    assert_eq!(stats.out_of_epoch, 0);
+}
+
+#[test]
+fn tampered_records_are_counted() {
+    let mut streams = Intercepted::new(InvalidRecordPolicy::Drop);
+
+    let mut tampered = streams.capture(b"tampered");
+    *tampered.last_mut().unwrap() ^= 1;
+    let valid = streams.capture(b"valid");
+    streams.forward(&tampered);
+    streams.forward(&valid);
+
+    assert_eq!(streams.recv().unwrap(), b"valid");
+
+    let stats = streams.server.record_stats();
+    assert_eq!(stats.bad_mac, 1);
+    assert_eq!(stats.replayed, 0);
+}
+
+#[test]
+fn records_of_unknown_epochs_are_counted() {
+    let mut streams = Intercepted::new(InvalidRecordPolicy::Drop);
+
+    let mut foreign = streams.capture(b"foreign");
+    foreign[3..5].copy_from_slice(&9u16.to_be_bytes());
+    let valid = streams.capture(b"valid");
+    streams.forward(&foreign);
+    streams.forward(&valid);
+
+    assert_eq!(streams.recv().unwrap(), b"valid");
+    assert_eq!(streams.server.record_stats().out_of_epoch, 1);
+}
+
+#[test]
+fn error_policy_reports_dropped_records() {
+    let mut streams = Intercepted::new(InvalidRecordPolicy::Error);
+
+    let first = streams.capture(b"first");
+    let second = streams.capture(b"second");
+    streams.forward(&first);
+    streams.forward(&first);
+    streams.forward(&second);
+
+    assert_eq!(streams.recv().unwrap(), b"first");
+    assert_eq!(streams.recv().unwrap(), b"second");
+    let error = streams.recv().unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+    let third = streams.capture(b"third");
+    streams.forward(&third);
+    assert_eq!(streams.recv().unwrap(), b"third");
+}
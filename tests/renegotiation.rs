@@ -0,0 +1,110 @@
+mod common;
+
+use std::{thread, time::Duration};
+use udp_dtls::{AlertDescription, DtlsAcceptor, DtlsConnector, MAX_DATAGRAM_SIZE};
+
+fn connector() -> udp_dtls::DtlsConnectorBuilder {
+    let mut builder = DtlsConnector::builder();
+    builder.add_root_certificate(common::root_certificate());
+    builder
+}
+
+fn acceptor() -> udp_dtls::DtlsAcceptorBuilder {
+    let mut builder = DtlsAcceptor::builder(common::identity());
+    builder.allow_client_renegotiation(true);
+    builder
+}
+
+#[test]
+fn client_renegotiates() {
+    let acceptor = acceptor().build().unwrap();
+    let (server, client) = common::handshake(acceptor, connector().build().unwrap());
+    let (mut server, mut client) = (server.unwrap(), client.unwrap());
+    assert_eq!(client.renegotiations(), 0);
+
+    let server = thread::spawn(move || {
+        let mut buf = [0; MAX_DATAGRAM_SIZE];
+        let len = server.recv_datagram(&mut buf).unwrap();
+        buf[..len].to_vec()
+    });
+
+    client.renegotiate().unwrap();
+    assert_eq!(client.renegotiations(), 1);
+    client.send_datagram(b"after").unwrap();
+
+    assert_eq!(server.join().unwrap(), b"after");
+}
+
+#[test]
+fn refused_renegotiation_is_reported() {
+    let acceptor = DtlsAcceptor::builder(common::identity())
+        .refuse_renegotiation(true)
+        .build()
+        .unwrap();
+    let (server, client) = common::handshake(acceptor, connector().build().unwrap());
+    let (mut server, mut client) = (server.unwrap(), client.unwrap());
+
+    server
+        .get_mut()
+        .socket
+        .set_read_timeout(Some(Duration::from_millis(500)))
+        .unwrap();
+    let server = thread::spawn(move || {
+        let mut buf = [0; MAX_DATAGRAM_SIZE];
+        let _ = server.recv_datagram(&mut buf);
+    });
+
+    assert!(client.renegotiate().is_err());
+    let alert = client.received_alert().unwrap();
+    assert_eq!(alert.description(), AlertDescription::NoRenegotiation);
+
+    server.join().unwrap();
+}
+
+#[test]
+fn client_renegotiation_is_refused_by_default() {
+    if openssl::version::number() < 0x3000_0000 {
+        return;
+    }
+    let acceptor = DtlsAcceptor::builder(common::identity()).build().unwrap();
+    let (server, client) = common::handshake(acceptor, connector().build().unwrap());
+    let (mut server, mut client) = (server.unwrap(), client.unwrap());
+
+    server
+        .get_mut()
+        .socket
+        .set_read_timeout(Some(Duration::from_millis(500)))
+        .unwrap();
+    let server = thread::spawn(move || {
+        let mut buf = [0; MAX_DATAGRAM_SIZE];
+        let _ = server.recv_datagram(&mut buf);
+    });
+
+    assert!(client.renegotiate().is_err());
+    let alert = client.received_alert().unwrap();
+    assert_eq!(alert.description(), AlertDescription::NoRenegotiation);
+
+    server.join().unwrap();
+}
+
+#[test]
+fn sessions_are_rekeyed_after_record_limit() {
+    let acceptor = acceptor().build().unwrap();
+    let connector = connector().rekey_after_records(Some(3)).build().unwrap();
+    let (server, client) = common::handshake(acceptor, connector);
+    let (mut server, mut client) = (server.unwrap(), client.unwrap());
+
+    let server = thread::spawn(move || {
+        let mut buf = [0; MAX_DATAGRAM_SIZE];
+        for _ in 0..5 {
+            server.recv_datagram(&mut buf).unwrap();
+        }
+    });
+
+    for i in 0..5 {
+        client.send_datagram(&[i]).unwrap();
+    }
+    server.join().unwrap();
+
+    assert_eq!(client.renegotiations(), 1);
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use udp_dtls::{ConnectorIdentity, DtlsAcceptor, DtlsConnector, PskIdentity};
+
+#[test]
+fn keying_material_is_shared_and_redacted() {
+    let acceptor = DtlsAcceptor::builder(common::identity()).build().unwrap();
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .build()
+        .unwrap();
+    let (server, client) = common::handshake(acceptor, connector);
+    let (server, client) = (server.unwrap(), client.unwrap());
+
+    let material = client.keying_material(60).unwrap();
+    assert_eq!(material.len(), 60);
+    assert_eq!(*material, *server.keying_material(60).unwrap());
+    assert_ne!(*material, [0; 60][..]);
+    assert_eq!(
+        format!("{:?}", material),
+        "KeyingMaterial(<60 bytes redacted>)"
+    );
+}
+
+#[test]
+fn identities_do_not_print_their_keys() {
+    let psk = PskIdentity::new(b"client", b"secret key");
+    let debug = format!("{:?}", ConnectorIdentity::Psk(psk));
+    assert!(debug.contains("client"), "{}", debug);
+    assert!(!debug.contains("secret"), "{}", debug);
+
+    let debug = format!("{:?}", common::identity());
+    assert!(debug.contains("foobar.com"), "{}", debug);
+    assert!(debug.contains("<redacted>"), "{}", debug);
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use std::{
+    io::{Read, Write},
+    net::UdpSocket,
+    thread,
+};
+use udp_dtls::{
+    DtlsAcceptor, DtlsConnector, DtlsSession, ErrorKind, UdpChannel, MAX_DATAGRAM_SIZE,
+};
+
+fn acceptor() -> DtlsAcceptor {
+    DtlsAcceptor::builder(common::identity()).build().unwrap()
+}
+
+fn connector() -> DtlsConnector {
+    DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .build()
+        .unwrap()
+}
+
+/// Moves all queued datagrams from `from` to `to`, returning how many were moved.
+fn pump(from: &mut DtlsSession, to: &mut DtlsSession) -> udp_dtls::Result<usize> {
+    let mut moved = 0;
+    while let Some(datagram) = from.poll_datagram() {
+        to.handle_datagram(&datagram)?;
+        moved += 1;
+    }
+    Ok(moved)
+}
+
+fn handshake(client: &mut DtlsSession, server: &mut DtlsSession) {
+    while client.is_handshaking() || server.is_handshaking() {
+        let moved = pump(client, server).unwrap() + pump(server, client).unwrap();
+        assert!(moved > 0, "handshake stalled");
+    }
+}
+
+#[test]
+fn sessions_exchange_data_in_memory() {
+    let mut client = connector().connect_session("foobar.com").unwrap();
+    let mut server = acceptor().accept_session().unwrap();
+    assert!(client.has_datagrams());
+    assert!(!server.has_datagrams());
+
+    let mut buf = [0; MAX_DATAGRAM_SIZE];
+    assert_eq!(
+        server.read(&mut buf).unwrap_err().kind(),
+        std::io::ErrorKind::WouldBlock
+    );
+
+    handshake(&mut client, &mut server);
+    assert!(client.stream().peer_certificate().unwrap().is_some());
+
+    client.write_all(b"hello").unwrap();
+    assert_eq!(pump(&mut client, &mut server).unwrap(), 1);
+    let read = server.read(&mut buf).unwrap();
+    assert_eq!(&buf[..read], b"hello");
+    assert_eq!(
+        server.read(&mut buf).unwrap_err().kind(),
+        std::io::ErrorKind::WouldBlock
+    );
+
+    server.stream_mut().send_datagram(b"world").unwrap();
+    pump(&mut server, &mut client).unwrap();
+    let read = client.stream_mut().recv_datagram(&mut buf).unwrap();
+    assert_eq!(&buf[..read], b"world");
+}
+
+#[test]
+fn lost_flights_are_retransmitted() {
+    let mut client = connector().connect_session("foobar.com").unwrap();
+    let mut server = acceptor().accept_session().unwrap();
+
+    // the client hello is lost
+    while client.poll_datagram().is_some() {}
+    assert!(!client.handle_timeout().unwrap());
+
+    let timeout = client.timeout().unwrap();
+    thread::sleep(timeout);
+    assert!(client.handle_timeout().unwrap());
+    assert!(client.has_datagrams());
+
+    handshake(&mut client, &mut server);
+}
+
+#[test]
+fn handshake_failures_are_returned() {
+    let connector = DtlsConnector::builder().build().unwrap();
+    let mut client = connector.connect_session("foobar.com").unwrap();
+    let mut server = acceptor().accept_session().unwrap();
+
+    let error = loop {
+        pump(&mut client, &mut server).unwrap();
+        if let Err(e) = pump(&mut server, &mut client) {
+            break e;
+        }
+    };
+    assert!(matches!(
+        error.kind(),
+        ErrorKind::CertificateVerification(_)
+    ));
+}
+
+#[test]
+fn sessions_talk_to_streams() {
+    let server_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let client_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let server_channel = UdpChannel {
+        remote_addr: client_socket.local_addr().unwrap(),
+        socket: server_socket.try_clone().unwrap(),
+    };
+
+    let server = thread::spawn(move || {
+        let mut stream = acceptor().accept(server_channel).unwrap();
+        let mut buf = [0; MAX_DATAGRAM_SIZE];
+        let read = stream.recv_datagram(&mut buf).unwrap();
+        stream.send_datagram(&buf[..read]).unwrap();
+    });
+
+    client_socket
+        .connect(server_socket.local_addr().unwrap())
+        .unwrap();
+    let mut client = connector().connect_session("foobar.com").unwrap();
+    let mut buf = [0; MAX_DATAGRAM_SIZE];
+    while client.is_handshaking() {
+        while let Some(datagram) = client.poll_datagram() {
+            client_socket.send(&datagram).unwrap();
+        }
+        let len = client_socket.recv(&mut buf).unwrap();
+        client.handle_datagram(&buf[..len]).unwrap();
+    }
+    while let Some(datagram) = client.poll_datagram() {
+        client_socket.send(&datagram).unwrap();
+    }
+
+    client.write_all(b"echo").unwrap();
+    client_socket
+        .send(&client.poll_datagram().unwrap())
+        .unwrap();
+    let len = client_socket.recv(&mut buf).unwrap();
+    client.handle_datagram(&buf[..len]).unwrap();
+
+    let read = client.read(&mut buf).unwrap();
+    assert_eq!(&buf[..read], b"echo");
+    server.join().unwrap();
+}
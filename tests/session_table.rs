@@ -0,0 +1,195 @@
+mod common;
+
+use std::{
+    io::{Read, Write},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+use udp_dtls::{
+    DtlsAcceptor, DtlsConnector, DtlsSession, EvictionReason, SessionTable, SessionTableBuilder,
+    MAX_DATAGRAM_SIZE,
+};
+
+fn builder() -> SessionTableBuilder {
+    let acceptor = DtlsAcceptor::builder(common::identity()).build().unwrap();
+    SessionTable::builder(acceptor)
+}
+
+fn client() -> DtlsSession {
+    DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .build()
+        .unwrap()
+        .connect_session("foobar.com")
+        .unwrap()
+}
+
+fn peer(port: u16) -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], port))
+}
+
+/// Runs the handshake of `client` with the table as `peer`.
+fn handshake(table: &mut SessionTable, peer: SocketAddr, client: &mut DtlsSession) {
+    while client.is_handshaking() || table.get_mut(&peer).is_some_and(|s| s.is_handshaking()) {
+        let mut moved = 0;
+        while let Some(datagram) = client.poll_datagram() {
+            table.handle_datagram(peer, &datagram).unwrap();
+            moved += 1;
+        }
+        if let Some(session) = table.get_mut(&peer) {
+            while let Some(datagram) = session.poll_datagram() {
+                client.handle_datagram(&datagram).unwrap();
+                moved += 1;
+            }
+        }
+        assert!(moved > 0, "handshake stalled");
+    }
+}
+
+type Evictions = Arc<Mutex<Vec<(SocketAddr, EvictionReason)>>>;
+
+fn recorder() -> (
+    Evictions,
+    impl Fn(SocketAddr, &DtlsSession, EvictionReason) + Send + Sync + 'static,
+) {
+    let evicted = Arc::new(Mutex::new(Vec::new()));
+    let record = evicted.clone();
+    let callback = move |peer, _: &DtlsSession, reason| record.lock().unwrap().push((peer, reason));
+    (evicted, callback)
+}
+
+#[test]
+fn sessions_are_created_per_peer() {
+    let created = Arc::new(Mutex::new(Vec::new()));
+    let record = created.clone();
+    let mut table = builder()
+        .on_session_created(move |peer, _| record.lock().unwrap().push(peer))
+        .build();
+    assert!(table.is_empty());
+
+    let mut first = client();
+    let mut second = client();
+    handshake(&mut table, peer(1), &mut first);
+    handshake(&mut table, peer(2), &mut second);
+    assert_eq!(table.len(), 2);
+    assert_eq!(*created.lock().unwrap(), vec![peer(1), peer(2)]);
+
+    first.write_all(b"hello").unwrap();
+    let datagram = first.poll_datagram().unwrap();
+    let session = table.handle_datagram(peer(1), &datagram).unwrap().unwrap();
+    let mut buf = [0; MAX_DATAGRAM_SIZE];
+    let read = session.read(&mut buf).unwrap();
+    assert_eq!(&buf[..read], b"hello");
+
+    assert!(table.remove(&peer(2)).is_some());
+    assert_eq!(table.len(), 1);
+}
+
+#[test]
+fn idle_sessions_are_evicted() {
+    let (evicted, callback) = recorder();
+    let mut table = builder()
+        .idle_timeout(Some(Duration::from_millis(200)))
+        .on_session_evicted(callback)
+        .build();
+
+    let mut idle = client();
+    handshake(&mut table, peer(1), &mut idle);
+    assert!(table.next_timeout().unwrap() <= Duration::from_millis(200));
+    assert_eq!(table.handle_timeouts(), 0);
+
+    thread::sleep(Duration::from_millis(250));
+    assert_eq!(table.next_timeout(), Some(Duration::from_secs(0)));
+    assert_eq!(table.handle_timeouts(), 1);
+    assert!(table.is_empty());
+    assert_eq!(
+        *evicted.lock().unwrap(),
+        vec![(peer(1), EvictionReason::Idle)]
+    );
+}
+
+#[test]
+fn least_recently_active_session_is_evicted_at_capacity() {
+    let (evicted, callback) = recorder();
+    let mut table = builder()
+        .max_sessions(Some(2))
+        .on_session_evicted(callback)
+        .build();
+
+    let mut first = client();
+    let mut second = client();
+    handshake(&mut table, peer(1), &mut first);
+    handshake(&mut table, peer(2), &mut second);
+
+    // the first session becomes the most recently active one
+    first.write_all(b"ping").unwrap();
+    let datagram = first.poll_datagram().unwrap();
+    table.handle_datagram(peer(1), &datagram).unwrap();
+
+    let mut third = client();
+    handshake(&mut table, peer(3), &mut third);
+    assert_eq!(table.len(), 2);
+    assert!(table.get_mut(&peer(1)).is_some());
+    assert!(table.get_mut(&peer(2)).is_none());
+    assert_eq!(
+        *evicted.lock().unwrap(),
+        vec![(peer(2), EvictionReason::Capacity)]
+    );
+}
+
+#[test]
+fn handshakes_are_rate_limited_per_address() {
+    let mut table = builder()
+        .handshake_rate_limit(Some((2, Duration::from_millis(300))))
+        .build();
+
+    for port in 1..=2 {
+        let datagram = client().poll_datagram().unwrap();
+        assert!(table
+            .handle_datagram(peer(port), &datagram)
+            .unwrap()
+            .is_some());
+    }
+    let datagram = client().poll_datagram().unwrap();
+    assert!(table.handle_datagram(peer(3), &datagram).unwrap().is_none());
+    assert_eq!(table.len(), 2);
+
+    // existing sessions and other addresses are not limited
+    assert!(table.handle_datagram(peer(1), &datagram).unwrap().is_some());
+    let other = SocketAddr::from(([127, 0, 0, 2], 1));
+    assert!(table.handle_datagram(other, &datagram).unwrap().is_some());
+
+    thread::sleep(Duration::from_millis(350));
+    assert!(table.handle_datagram(peer(3), &datagram).unwrap().is_some());
+}
+
+#[test]
+fn failed_handshakes_are_evicted() {
+    let (evicted, callback) = recorder();
+    let mut table = builder().on_session_evicted(callback).build();
+
+    // the client rejects the server's certificate and sends an alert
+    let mut client = DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .build()
+        .unwrap()
+        .connect_session("example.com")
+        .unwrap();
+    let hello = client.poll_datagram().unwrap();
+    let session = table.handle_datagram(peer(1), &hello).unwrap().unwrap();
+    let mut rejected = false;
+    while let Some(datagram) = session.poll_datagram() {
+        rejected |= client.handle_datagram(&datagram).is_err();
+    }
+    assert!(rejected);
+
+    let alert = client.poll_datagram().unwrap();
+    assert!(table.handle_datagram(peer(1), &alert).is_err());
+    assert!(table.is_empty());
+    assert_eq!(
+        *evicted.lock().unwrap(),
+        vec![(peer(1), EvictionReason::HandshakeFailed)]
+    );
+}
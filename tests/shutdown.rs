@@ -0,0 +1,81 @@
+mod common;
+
+use std::{io, time::Duration};
+use udp_dtls::{
+    DtlsAcceptor, DtlsConnector, DtlsStream, ReadTimeout, ShutdownResult, UdpChannel,
+    MAX_DATAGRAM_SIZE,
+};
+
+fn streams() -> (DtlsStream<UdpChannel>, DtlsStream<UdpChannel>) {
+    let acceptor = DtlsAcceptor::builder(common::identity()).build().unwrap();
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .build()
+        .unwrap();
+
+    let (server, client) = common::handshake(acceptor, connector);
+    (server.unwrap(), client.unwrap())
+}
+
+#[test]
+fn shutdown_reports_both_steps() {
+    let (mut server, mut client) = streams();
+
+    assert_eq!(client.shutdown().unwrap(), ShutdownResult::Sent);
+    assert!(!server.peer_closed());
+
+    let mut buf = [0; MAX_DATAGRAM_SIZE];
+    assert_eq!(server.recv_datagram(&mut buf).unwrap(), 0);
+    assert!(server.peer_closed());
+    assert_eq!(server.shutdown().unwrap(), ShutdownResult::Received);
+
+    assert_eq!(
+        client.shutdown_timeout(Duration::from_secs(5)).unwrap(),
+        ShutdownResult::Received
+    );
+    assert!(client.peer_closed());
+}
+
+#[test]
+fn shutdown_timeout_discards_data_before_close_notify() {
+    let (mut server, mut client) = streams();
+
+    server.send_datagram(b"late").unwrap();
+    assert_eq!(server.shutdown().unwrap(), ShutdownResult::Sent);
+
+    assert_eq!(
+        client.shutdown_timeout(Duration::from_secs(5)).unwrap(),
+        ShutdownResult::Received
+    );
+}
+
+#[test]
+fn shutdown_timeout_expires_without_close_notify() {
+    let (_server, mut client) = streams();
+    let read_timeout = Some(Duration::from_secs(3));
+    client.get_mut().set_read_timeout(read_timeout).unwrap();
+
+    assert_eq!(
+        client.shutdown_timeout(Duration::from_millis(100)).unwrap(),
+        ShutdownResult::Sent
+    );
+    assert!(!client.peer_closed());
+    assert_eq!(client.get_ref().read_timeout().unwrap(), read_timeout);
+}
+
+#[test]
+fn nonblocking_shutdown_would_block() {
+    let (mut server, mut client) = streams();
+    client.get_ref().socket.set_nonblocking(true).unwrap();
+
+    assert_eq!(client.shutdown().unwrap(), ShutdownResult::Sent);
+    let error = client.shutdown().unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::WouldBlock);
+
+    let mut buf = [0; MAX_DATAGRAM_SIZE];
+    assert_eq!(server.recv_datagram(&mut buf).unwrap(), 0);
+    assert_eq!(server.shutdown().unwrap(), ShutdownResult::Received);
+
+    client.get_ref().socket.set_nonblocking(false).unwrap();
+    assert_eq!(client.shutdown().unwrap(), ShutdownResult::Received);
+}
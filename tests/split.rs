@@ -0,0 +1,121 @@
+mod common;
+
+use std::{
+    io, thread,
+    time::{Duration, Instant},
+};
+use udp_dtls::{DtlsAcceptor, DtlsConnector, ReadTimeout, MAX_DATAGRAM_SIZE};
+
+const MESSAGES: usize = 200;
+
+fn streams() -> (
+    udp_dtls::DtlsStream<udp_dtls::UdpChannel>,
+    udp_dtls::DtlsStream<udp_dtls::UdpChannel>,
+) {
+    let acceptor = DtlsAcceptor::builder(common::identity()).build().unwrap();
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .build()
+        .unwrap();
+    let (server, client) = common::handshake(acceptor, connector);
+    (server.unwrap(), client.unwrap())
+}
+
+#[test]
+fn halves_send_and_receive_concurrently() {
+    let (server, client) = streams();
+
+    let peers = [server, client].map(|stream| {
+        let (mut read, mut write) = stream.split();
+        let sender = thread::spawn(move || {
+            for i in 0..MESSAGES {
+                write.send_datagram(&(i as u32).to_be_bytes()).unwrap();
+                thread::sleep(Duration::from_micros(200));
+            }
+        });
+        let receiver = thread::spawn(move || {
+            read.set_read_timeout(Some(Duration::from_secs(2)));
+            let mut buf = [0; MAX_DATAGRAM_SIZE];
+            let mut received = 0;
+            while received < MESSAGES {
+                match read.recv_datagram(&mut buf) {
+                    Ok(len) => {
+                        assert_eq!(len, 4);
+                        received += 1;
+                    }
+                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(e) => panic!("{}", e),
+                }
+            }
+            received
+        });
+        (sender, receiver)
+    });
+
+    for (sender, receiver) in peers {
+        sender.join().unwrap();
+        assert_eq!(receiver.join().unwrap(), MESSAGES);
+    }
+}
+
+#[test]
+fn blocked_reads_do_not_hold_up_writes() {
+    let (mut server, client) = streams();
+    let (mut read, mut write) = client.split();
+
+    // the reader waits for the peer indefinitely
+    read.set_read_timeout(None);
+    let reader = thread::spawn(move || {
+        let mut buf = [0; MAX_DATAGRAM_SIZE];
+        let len = read.recv_datagram(&mut buf).unwrap();
+        buf[..len].to_vec()
+    });
+    thread::sleep(Duration::from_millis(50));
+
+    let start = Instant::now();
+    for _ in 0..10 {
+        write.send_datagram(b"ping").unwrap();
+    }
+    assert!(start.elapsed() < Duration::from_millis(500));
+
+    let mut buf = [0; MAX_DATAGRAM_SIZE];
+    for _ in 0..10 {
+        let len = server.recv_datagram(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"ping");
+    }
+    server.send_datagram(b"pong").unwrap();
+    assert_eq!(reader.join().unwrap(), b"pong");
+}
+
+#[test]
+fn reads_time_out() {
+    let (_server, client) = streams();
+    let (mut read, write) = client.split();
+    assert_eq!(read.read_timeout(), None);
+
+    read.set_read_timeout(Some(Duration::from_millis(100)));
+    let start = Instant::now();
+    let mut buf = [0; MAX_DATAGRAM_SIZE];
+    let error = read.recv_datagram(&mut buf).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::WouldBlock);
+    assert!(start.elapsed() >= Duration::from_millis(100));
+
+    // the halves share the session
+    let peer = write.lock().unwrap().peer_certificate().unwrap();
+    assert!(peer.is_some());
+    assert!(read.clone().lock().is_ok());
+}
+
+#[test]
+fn reads_restore_the_read_timeout_of_the_stream() {
+    let (mut server, client) = streams();
+    let (mut read, _write) = client.split();
+
+    server.send_datagram(b"ping").unwrap();
+    let mut buf = [0; MAX_DATAGRAM_SIZE];
+    let read_len = read.recv_datagram(&mut buf).unwrap();
+    assert_eq!(&buf[..read_len], b"ping");
+
+    let stream = read.lock().unwrap();
+    assert_eq!(stream.get_ref().read_timeout().unwrap(), None);
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::sync::{Mutex, Once};
+use udp_dtls::{DtlsAcceptor, DtlsConnector};
+
+/// Collects the trace lines of all tests in this binary.
+struct TraceLogger(Mutex<Vec<String>>);
+
+impl Log for TraceLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target() == "udp_dtls::trace" && metadata.level() <= Level::Debug
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: TraceLogger = TraceLogger(Mutex::new(Vec::new()));
+
+/// Returns the trace lines logged so far for the given label.
+fn lines(label: &str) -> Vec<String> {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(LevelFilter::Debug);
+    });
+
+    LOGGER
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|line| line.starts_with(label))
+        .cloned()
+        .collect()
+}
+
+fn contains(lines: &[String], text: &str) -> bool {
+    lines.iter().any(|line| line.contains(text))
+}
+
+#[test]
+fn handshake_is_traced_with_peer_address() {
+    lines("");
+    let acceptor = DtlsAcceptor::builder(common::identity())
+        .trace_handshake(true)
+        .build()
+        .unwrap();
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .trace_handshake(true)
+        .build()
+        .unwrap();
+    let (server_channel, client_channel) = common::channel_pair();
+    let client_label = format!("client {}", client_channel.remote_addr);
+    let server_label = format!("server {}", server_channel.remote_addr);
+
+    let (client_addr, server_addr) = (server_channel.remote_addr, client_channel.remote_addr);
+    let server =
+        std::thread::spawn(move || acceptor.accept_from(server_channel, client_addr).unwrap());
+    connector
+        .connect_to("foobar.com", client_channel, server_addr)
+        .unwrap();
+    server.join().unwrap();
+
+    let client = lines(&client_label);
+    assert!(contains(&client, ": starting handshake"));
+    assert!(contains(&client, ": sent ClientHello"));
+    assert!(contains(&client, ": received ServerHello"));
+    assert!(contains(&client, ": state "));
+    assert!(contains(&client, ": handshake done"));
+
+    let server = lines(&server_label);
+    assert!(contains(&server, ": received ClientHello"));
+    assert!(contains(&server, ": sent Finished"));
+    assert!(contains(&server, ": handshake done"));
+}
+
+#[test]
+fn alerts_are_traced() {
+    lines("");
+    let acceptor = DtlsAcceptor::builder(common::identity())
+        .trace_handshake(true)
+        .build()
+        .unwrap();
+    let connector = DtlsConnector::builder()
+        .trace_handshake(true)
+        .build()
+        .unwrap();
+    let (server_channel, client_channel) = common::channel_pair();
+    let client_label = format!("client {}", client_channel.remote_addr);
+    let server_label = format!("server {}", server_channel.remote_addr);
+
+    let (client_addr, server_addr) = (server_channel.remote_addr, client_channel.remote_addr);
+    let server =
+        std::thread::spawn(move || acceptor.accept_from(server_channel, client_addr).is_err());
+    assert!(connector
+        .connect_to("foobar.com", client_channel, server_addr)
+        .is_err());
+    assert!(server.join().unwrap());
+
+    assert!(contains(
+        &lines(&client_label),
+        ": sent fatal alert: unknown CA"
+    ));
+    assert!(contains(
+        &lines(&server_label),
+        ": received fatal alert: unknown CA"
+    ));
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use openssl::x509::X509;
+use std::{fs, path::PathBuf};
+use udp_dtls::{CrlCheck, DtlsAcceptor, DtlsConnector, DtlsConnectorBuilder, Error, ErrorKind};
+
+const X509_V_ERR_UNABLE_TO_GET_CRL: i32 = 3;
+const X509_V_ERR_CERT_REVOKED: i32 = 23;
+
+fn acceptor() -> DtlsAcceptor {
+    DtlsAcceptor::builder(common::identity()).build().unwrap()
+}
+
+fn handshake(connector: &DtlsConnectorBuilder) -> Result<(), Error> {
+    let (_, client) = common::handshake(acceptor(), connector.build()?);
+    client.map(drop)
+}
+
+fn without_system_roots() -> DtlsConnectorBuilder {
+    let mut builder = DtlsConnector::builder();
+    builder.use_system_roots(false);
+    builder
+}
+
+#[test]
+fn peers_are_rejected_without_roots() {
+    let error = handshake(&without_system_roots()).unwrap_err();
+    assert!(matches!(
+        error.kind(),
+        ErrorKind::CertificateVerification(_)
+    ));
+}
+
+#[test]
+fn ca_bundle_files_are_trusted() {
+    let mut builder = without_system_roots();
+    builder.add_root_certificates_file("test/server.pem");
+    handshake(&builder).unwrap();
+}
+
+#[test]
+fn hashed_ca_directories_are_trusted() {
+    let pem = fs::read("test/server.pem").unwrap();
+    let hash = X509::from_pem(&pem).unwrap().subject_name_hash();
+    let dir = std::env::temp_dir().join(format!("udp-dtls-roots-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join(format!("{:08x}.0", hash)), pem).unwrap();
+
+    let mut builder = without_system_roots();
+    builder.add_root_certificates_dir(&dir);
+    let result = handshake(&builder);
+    fs::remove_dir_all(&dir).unwrap();
+    result.unwrap();
+}
+
+#[test]
+fn missing_files_fail_the_build() {
+    let missing = PathBuf::from("test/missing.pem");
+
+    let mut builder = without_system_roots();
+    builder.add_root_certificates_file(&missing);
+    assert!(
+        matches!(builder.build(), Err(Error::Configuration(ref m)) if m.contains("missing.pem"))
+    );
+
+    let mut builder = without_system_roots();
+    builder.add_crl_file(&missing);
+    assert!(
+        matches!(builder.build(), Err(Error::Configuration(ref m)) if m.contains("missing.pem"))
+    );
+}
+
+/// Accepts with `common::leaf_identity`, the certificate the CRLs in `test/` apply to.
+fn leaf_handshake(connector: &DtlsConnectorBuilder) -> Result<(), Error> {
+    let acceptor = DtlsAcceptor::builder(common::leaf_identity())
+        .build()
+        .unwrap();
+    let (_, client) = common::handshake(acceptor, connector.build()?);
+    client.map(drop)
+}
+
+#[test]
+fn revoked_certificates_are_rejected() {
+    let mut builder = without_system_roots();
+    builder
+        .add_root_certificate(common::test_ca())
+        .add_crl_file("test/leaf-revoked.crl.pem")
+        .crl_check(Some(CrlCheck::Leaf));
+    let error = leaf_handshake(&builder).unwrap_err();
+    assert!(matches!(
+        error.kind(),
+        ErrorKind::CertificateVerification(result) if result.as_raw() == X509_V_ERR_CERT_REVOKED
+    ));
+
+    let mut builder = without_system_roots();
+    builder
+        .add_root_certificate(common::test_ca())
+        .add_crl_file("test/leaf.crl.pem")
+        .crl_check(Some(CrlCheck::Chain));
+    leaf_handshake(&builder).unwrap();
+}
+
+#[test]
+fn crl_check_requires_a_crl() {
+    let mut builder = without_system_roots();
+    builder
+        .add_root_certificate(common::test_ca())
+        .crl_check(Some(CrlCheck::Leaf));
+    let error = leaf_handshake(&builder).unwrap_err();
+    assert!(matches!(
+        error.kind(),
+        ErrorKind::CertificateVerification(result) if result.as_raw() == X509_V_ERR_UNABLE_TO_GET_CRL
+    ));
+
+    let mut builder = without_system_roots();
+    builder
+        .add_root_certificate(common::test_ca())
+        .add_crl_file("test/leaf.crl.pem")
+        .crl_check(Some(CrlCheck::Leaf));
+    leaf_handshake(&builder).unwrap();
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    net::{SocketAddr, UdpSocket},
+    thread,
+    time::Duration,
+};
+use udp_dtls::{DtlsAcceptor, DtlsConnector, DtlsSession, UdpBatch, UdpChannel, MAX_DATAGRAM_SIZE};
+
+#[test]
+fn datagrams_are_batched() {
+    let mut sender = UdpBatch::new(UdpSocket::bind("127.0.0.1:0").unwrap(), 4);
+    let mut receiver = UdpBatch::new(UdpSocket::bind("127.0.0.1:0").unwrap(), 16);
+    let receiver_addr = receiver.socket().local_addr().unwrap();
+
+    for i in 0..10u8 {
+        sender.queue(receiver_addr, vec![i; usize::from(i) + 1]);
+    }
+    assert_eq!(sender.pending(), 10);
+    assert_eq!(sender.flush().unwrap(), 10);
+    assert_eq!(sender.pending(), 0);
+
+    let sender_addr = sender.socket().local_addr().unwrap();
+    let mut received = Vec::new();
+    while received.len() < 10 {
+        receiver.recv().unwrap();
+        for (peer, datagram) in receiver.received() {
+            assert_eq!(peer, sender_addr);
+            received.push(datagram.to_vec());
+        }
+    }
+    for (i, datagram) in received.iter().enumerate() {
+        assert_eq!(datagram, &vec![i as u8; i + 1]);
+    }
+}
+
+#[test]
+fn nonblocking_sockets_would_block() {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket.set_nonblocking(true).unwrap();
+    let mut batch = UdpBatch::new(socket, 8);
+    assert_eq!(
+        batch.recv().unwrap_err().kind(),
+        std::io::ErrorKind::WouldBlock
+    );
+}
+
+#[test]
+fn servers_serve_many_clients_per_batch() {
+    const CLIENTS: usize = 4;
+
+    let acceptor = DtlsAcceptor::builder(common::identity()).build().unwrap();
+    let connector = DtlsConnector::builder()
+        .add_root_certificate(common::root_certificate())
+        .build()
+        .unwrap();
+
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_millis(100)))
+        .unwrap();
+    let server_addr = socket.local_addr().unwrap();
+    let mut server = UdpBatch::new(socket, 32);
+
+    let clients = (0..CLIENTS)
+        .map(|i| {
+            let connector = connector.clone();
+            thread::spawn(move || {
+                let channel = UdpChannel {
+                    socket: UdpSocket::bind("127.0.0.1:0").unwrap(),
+                    remote_addr: server_addr,
+                };
+                let mut stream = connector.connect("foobar.com", channel).unwrap();
+                let message = format!("client {}", i);
+                stream.send_datagram(message.as_bytes()).unwrap();
+
+                let mut buf = [0; MAX_DATAGRAM_SIZE];
+                let len = stream.recv_datagram(&mut buf).unwrap();
+                assert_eq!(&buf[..len], message.as_bytes());
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut sessions: HashMap<SocketAddr, DtlsSession> = HashMap::new();
+    let mut echoed = 0;
+    let mut buf = [0; MAX_DATAGRAM_SIZE];
+    while echoed < CLIENTS {
+        match server.recv() {
+            Ok(_) => {}
+            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+            Err(e) => panic!("{}", e),
+        }
+        let received = server
+            .received()
+            .map(|(peer, datagram)| (peer, datagram.to_vec()))
+            .collect::<Vec<_>>();
+        for (peer, datagram) in received {
+            let session = sessions
+                .entry(peer)
+                .or_insert_with(|| acceptor.accept_session().unwrap());
+            session.handle_datagram(&datagram).unwrap();
+            if !session.is_handshaking() {
+                while let Ok(len) = session.read(&mut buf) {
+                    session.write_all(&buf[..len]).unwrap();
+                    echoed += 1;
+                }
+            }
+        }
+
+        for (peer, session) in &mut sessions {
+            if session.timeout() == Some(Duration::from_secs(0)) {
+                session.handle_timeout().unwrap();
+            }
+            server.queue_session(*peer, session);
+        }
+        server.flush().unwrap();
+    }
+
+    for client in clients {
+        client.join().unwrap();
+    }
+}